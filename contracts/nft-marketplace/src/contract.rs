@@ -1,34 +1,78 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, from_binary, to_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdError, StdResult, Uint128, WasmMsg,
+    coin, from_binary, to_binary, Addr, Api, BankMsg, Binary, BlockInfo, CosmosMsg, Deps, DepsMut,
+    Env, Event, MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128,
+    WasmMsg,
 };
 use cw2::set_contract_version;
-use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use cw721::Cw721ReceiveMsg;
-use cw_utils::must_pay;
+use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Denom, TokenInfoResponse};
+use cw721::{
+    AllNftInfoResponse, Cw721QueryMsg, Cw721ReceiveMsg, OperatorsResponse, OwnerOfResponse,
+};
+use cw721_base::{MinterResponse, QueryMsg as Cw721BaseQueryMsg};
+use cw_storage_plus::{Bound, Prefix};
+use cw_utils::{must_pay, Expiration};
+use nft::contract::Extension;
 use semver::Version;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
 use crate::msg::{
-    AskResponse, Cw20HookMsg, Cw721DepositResponse, Cw721HookMsg, ExecuteMsg, InstantiateMsg,
-    QueryMsg, GetAllAsksResponse,
+    AskResponse, AsksByPriceResponse, AsksResponse, AuctionResponse, AuctionsResponse,
+    BidsResponse, CollectionBidResponse, CollectionBidsResponse, CollectionResponse,
+    CollectionsResponse, ConfigResponse, CurrentPriceResponse, Cw20HookMsg, Cw721DepositResponse,
+    Cw721HookMsg, ExecuteMsg, FloorPriceResponse, GetAllAsksResponse, InstantiateMsg, MigrateMsg,
+    PauseStatusResponse, PaymentTokensResponse, QueryMsg, RoyaltyResponse, SealedAuctionResponse,
+    SealedBidsResponse, SortOrder, TraitBidResponse, TraitBidsResponse,
+};
+use crate::state::{
+    asks, auctions, bids, collection_bids, trait_bids, Ask, Auction, AuctionBid, Bid, BidKey,
+    CollectionBid, CollectionInfo, CollectionMode, Config, Cw721Deposits, MetadataCriteria,
+    PauseStatus, PaymentToken, PriceDecline, Royalty, SealedAuction, SealedBid, TraitBid,
+    COLLECTIONS, CONFIG, CW721_DEPOSITS, EXPIRES_AT_HEIGHT, EXPIRES_AT_TIME, PAUSE_STATUS,
+    PAYMENT_TOKENS, ROYALTIES, SEALED_AUCTIONS, SEALED_BIDS, TRAIT_BID_COUNT,
 };
-use crate::state::{Ask, Cw721Deposits, ASKS, CW721_DEPOSITS, Config, CONFIG};
 
 const CONTRACT_NAME: &str = "crates.io:nft-marketplace";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// first version with the current ask, bid and auction storage layout and indexes
+const STORAGE_LAYOUT_VERSION: &str = "0.2.0";
+
+// settings for pagination
+const DEFAULT_QUERY_LIMIT: u32 = 10;
+const MAX_QUERY_LIMIT: u32 = 30;
+
+// cw721-base caps the page size of its operator queries, so approvals are looked up
+// over a bounded number of pages
+const CW721_MAX_QUERY_LIMIT: u32 = 100;
+const MAX_OPERATOR_PAGES: u32 = 5;
+
+// settling a sealed-bid auction refunds or slashes every bid left, so their number is capped
+const MAX_SEALED_BIDS: u32 = 100;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    let admin = match msg.admin {
+        Some(admin) => validate_addr(deps.api, &admin)?,
+        None => info.sender,
+    };
+    validate_fees(msg.trading_fee_bps, msg.max_royalty_bps)?;
     let config = Config {
-        native_denom: msg.native_denom
+        native_denom: msg.native_denom,
+        native_denoms: msg.native_denoms,
+        admin: Some(admin),
+        pending_admin: None,
+        trading_fee_bps: msg.trading_fee_bps,
+        fee_recipient: validate_addr(deps.api, &msg.fee_recipient)?,
+        max_royalty_bps: msg.max_royalty_bps,
+        collection_mode: msg.collection_mode,
     };
     CONFIG.save(deps.storage, &config)?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -38,40 +82,357 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    match &msg {
+        ExecuteMsg::UpdateAsk { .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.listing, "listing")?
+        }
+        ExecuteMsg::SetAsk { collection, .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.listing, "listing")?;
+            assert_collection_allowed(deps.as_ref(), collection)?;
+        }
+        ExecuteMsg::PurchaseNative { collection, .. }
+        | ExecuteMsg::AcceptBid { collection, .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.purchasing, "purchasing")?;
+            assert_collection_allowed(deps.as_ref(), collection)?;
+        }
+        // settling stays open for blocked collections so escrowed NFTs and bids are released
+        ExecuteMsg::SettleAuction { .. } | ExecuteMsg::SettleSealedAuction { .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.purchasing, "purchasing")?
+        }
+        ExecuteMsg::SetBid { .. }
+        | ExecuteMsg::SetCollectionBid { .. }
+        | ExecuteMsg::SetTraitBid { .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.bidding, "bidding")?
+        }
+        ExecuteMsg::PlaceAuctionBid { collection, .. }
+        | ExecuteMsg::CommitSealedBid { collection, .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.bidding, "bidding")?;
+            assert_collection_allowed(deps.as_ref(), collection)?;
+        }
+        ExecuteMsg::ReturnExpiredListings { .. } | ExecuteMsg::RemoveStaleAsk { .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.withdrawals, "withdrawals")?
+        }
+        // owners can always take back their listings and bids, and reveals stay open so a
+        // pause cannot get honest sealed bids slashed
+        _ => {}
+    }
     match msg {
-        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, _env, info, cw20_msg),
-        ExecuteMsg::ReceiveNft(cw721_msg) => receive_cw721(deps, _env, info, cw721_msg),
+        ExecuteMsg::UpdateConfig {
+            native_denoms,
+            trading_fee_bps,
+            fee_recipient,
+            max_royalty_bps,
+            collection_mode,
+        } => execute_update_config(
+            deps,
+            info,
+            native_denoms,
+            trading_fee_bps,
+            fee_recipient,
+            max_royalty_bps,
+            collection_mode,
+        ),
+        ExecuteMsg::AddPaymentToken { address } => execute_add_payment_token(deps, info, address),
+        ExecuteMsg::RemovePaymentToken { address } => {
+            execute_remove_payment_token(deps, info, address)
+        }
+        ExecuteMsg::SetCollection {
+            address,
+            name,
+            verified,
+            blocked,
+        } => execute_set_collection(deps, info, address, name, verified, blocked),
+        ExecuteMsg::RemoveCollection { address } => execute_remove_collection(deps, info, address),
+        ExecuteMsg::UpdatePauseStatus {
+            listing,
+            purchasing,
+            bidding,
+            withdrawals,
+        } => execute_update_pause_status(deps, info, listing, purchasing, bidding, withdrawals),
+        ExecuteMsg::ProposeNewAdmin { admin } => execute_propose_new_admin(deps, info, admin),
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, info),
+        ExecuteMsg::RenounceAdmin {} => execute_renounce_admin(deps, info),
+        ExecuteMsg::SetRoyalty {
+            collection,
+            recipient,
+            share_bps,
+        } => execute_set_royalty(deps, info, collection, recipient, share_bps),
+        ExecuteMsg::RemoveRoyalty { collection } => execute_remove_royalty(deps, info, collection),
+        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, env, info, cw20_msg),
+        ExecuteMsg::ReceiveNft(cw721_msg) => receive_cw721(deps, env, info, cw721_msg),
         ExecuteMsg::PurchaseNative {
             collection,
             token_id,
-        } => execute_purchase_native(deps, info, collection, token_id),
+        } => execute_purchase_native(deps, env, info, collection, token_id),
         ExecuteMsg::RemoveListing {
             collection,
             token_id,
         } => execute_remove_listing(deps, info, collection, token_id),
+        ExecuteMsg::SetAsk {
+            collection,
+            token_id,
+            price,
+            payment,
+            expires,
+            payout_address,
+        } => execute_set_ask(
+            deps,
+            env,
+            info,
+            collection,
+            token_id,
+            price,
+            payment,
+            expires,
+            payout_address,
+        ),
+        ExecuteMsg::UpdateAsk {
+            collection,
+            token_id,
+            price,
+            payment,
+            expires,
+        } => execute_update_ask(
+            deps, env, info, collection, token_id, price, payment, expires,
+        ),
+        ExecuteMsg::ReturnExpiredListings { limit } => {
+            execute_return_expired_listings(deps, env, limit)
+        }
+        ExecuteMsg::RemoveStaleAsk {
+            collection,
+            token_id,
+        } => execute_remove_stale_ask(deps, env, collection, token_id),
+        ExecuteMsg::SetBid {
+            collection,
+            token_id,
+            expires,
+        } => execute_set_bid(deps, env, info, collection, token_id, expires),
+        ExecuteMsg::RemoveBid {
+            collection,
+            token_id,
+        } => execute_remove_bid(deps, info, collection, token_id),
+        ExecuteMsg::AcceptBid {
+            collection,
+            token_id,
+            bidder,
+        } => execute_accept_bid(deps, env, info, collection, token_id, bidder),
+        ExecuteMsg::SetCollectionBid {
+            collection,
+            expires,
+        } => execute_set_collection_bid(deps, env, info, collection, expires),
+        ExecuteMsg::RemoveCollectionBid { collection } => {
+            execute_remove_collection_bid(deps, info, collection)
+        }
+        ExecuteMsg::SetTraitBid {
+            collection,
+            criteria,
+            expires,
+        } => execute_set_trait_bid(deps, env, info, collection, criteria, expires),
+        ExecuteMsg::RemoveTraitBid { id } => execute_remove_trait_bid(deps, info, id),
+        ExecuteMsg::PlaceAuctionBid {
+            collection,
+            token_id,
+        } => execute_place_auction_bid(deps, env, info, collection, token_id),
+        ExecuteMsg::SettleAuction {
+            collection,
+            token_id,
+        } => execute_settle_auction(deps, env, collection, token_id),
+        ExecuteMsg::CommitSealedBid {
+            collection,
+            token_id,
+            commitment,
+        } => execute_commit_sealed_bid(deps, env, info, collection, token_id, commitment),
+        ExecuteMsg::RevealSealedBid {
+            collection,
+            token_id,
+            amount,
+            salt,
+        } => execute_reveal_sealed_bid(deps, env, info, collection, token_id, amount, salt),
+        ExecuteMsg::SettleSealedAuction {
+            collection,
+            token_id,
+        } => execute_settle_sealed_auction(deps, env, collection, token_id),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::Cw721Deposits { owner, collection } => {
-            to_binary(&query_cw721_deposits(deps, owner, collection)?)
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PauseStatus {} => to_binary(&query_pause_status(deps)?),
+        QueryMsg::PaymentTokens {} => to_binary(&query_payment_tokens(deps)?),
+        QueryMsg::Collection { address } => to_binary(&query_collection(deps, address)?),
+        QueryMsg::Collections { start_after, limit } => {
+            to_binary(&query_collections(deps, start_after, limit)?)
         }
+        QueryMsg::Royalty { collection } => to_binary(&query_royalty(deps, collection)?),
+        QueryMsg::Cw721Deposits {
+            owner,
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&query_cw721_deposits(
+            deps,
+            owner,
+            collection,
+            start_after,
+            limit,
+        )?),
         QueryMsg::Ask {
             collection,
             token_id,
         } => to_binary(&query_ask(deps, collection, token_id)?),
-        QueryMsg::GetAllAsks {} => to_binary(&query_all_asks(deps)?)
+        QueryMsg::GetAllAsks { start_after, limit } => {
+            to_binary(&query_all_asks(deps, env, start_after, limit)?)
+        }
+        QueryMsg::AsksBySeller {
+            seller,
+            start_after,
+            limit,
+        } => to_binary(&query_asks_by_seller(
+            deps,
+            env,
+            seller,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AsksByCollection {
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&query_asks_by_collection(
+            deps,
+            env,
+            collection,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AsksByPaymentToken {
+            payment_token,
+            start_after,
+            limit,
+        } => to_binary(&query_asks_by_payment_token(
+            deps,
+            env,
+            payment_token,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AsksByPrice {
+            collection,
+            payment_token,
+            min,
+            max,
+            order,
+            start_after,
+            limit,
+        } => to_binary(&query_asks_by_price(
+            deps,
+            env,
+            collection,
+            payment_token,
+            min,
+            max,
+            order,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::CurrentPrice {
+            collection,
+            token_id,
+        } => to_binary(&query_current_price(deps, env, collection, token_id)?),
+        QueryMsg::FloorPrice {
+            collection,
+            payment_token,
+        } => to_binary(&query_floor_price(deps, env, collection, payment_token)?),
+        QueryMsg::BidsForToken {
+            collection,
+            token_id,
+            start_after,
+            limit,
+        } => to_binary(&query_bids_for_token(
+            deps,
+            collection,
+            token_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::BidsByBidder {
+            bidder,
+            start_after,
+            limit,
+        } => to_binary(&query_bids_by_bidder(deps, bidder, start_after, limit)?),
+        QueryMsg::CollectionBid { collection, bidder } => {
+            to_binary(&query_collection_bid(deps, collection, bidder)?)
+        }
+        QueryMsg::TopCollectionBids {
+            collection,
+            payment_token,
+            start_after,
+            limit,
+        } => to_binary(&query_top_collection_bids(
+            deps,
+            collection,
+            payment_token,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::TraitBid { id } => to_binary(&query_trait_bid(deps, id)?),
+        QueryMsg::TraitBidsForCollection {
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&query_trait_bids_for_collection(
+            deps,
+            collection,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::TraitBidsByBidder {
+            bidder,
+            start_after,
+            limit,
+        } => to_binary(&query_trait_bids_by_bidder(
+            deps,
+            bidder,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Auction {
+            collection,
+            token_id,
+        } => to_binary(&query_auction(deps, collection, token_id)?),
+        QueryMsg::ActiveAuctions { start_after, limit } => {
+            to_binary(&query_active_auctions(deps, env, start_after, limit)?)
+        }
+        QueryMsg::EndedAuctions { start_after, limit } => {
+            to_binary(&query_ended_auctions(deps, env, start_after, limit)?)
+        }
+        QueryMsg::SealedAuction {
+            collection,
+            token_id,
+        } => to_binary(&query_sealed_auction(deps, collection, token_id)?),
+        QueryMsg::SealedBids {
+            collection,
+            token_id,
+            start_after,
+            limit,
+        } => to_binary(&query_sealed_bids(
+            deps,
+            collection,
+            token_id,
+            start_after,
+            limit,
+        )?),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: QueryMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let current_version = cw2::get_contract_version(deps.storage)?;
     if current_version.contract != CONTRACT_NAME {
         return Err(StdError::generic_err("Cannot upgrade to a different contract").into());
@@ -87,6 +448,18 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: QueryMsg) -> Result<Response, Con
     if version > new_version {
         return Err(StdError::generic_err("Cannot upgrade to a previous contract version").into());
     }
+    // older asks and bids cannot be read with the current layout and are missing from
+    // its indexes, such a deployment has to be replaced by a new instance
+    let layout_version: Version = STORAGE_LAYOUT_VERSION
+        .parse()
+        .map_err(|_| StdError::generic_err("Invalid contract version"))?;
+    if version < layout_version {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from version {}, the storage layout changed: deploy a new instance",
+            version
+        ))
+        .into());
+    }
     // if same version return
     if version == new_version {
         return Ok(Response::new());
@@ -97,156 +470,652 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: QueryMsg) -> Result<Response, Con
     Ok(Response::new())
 }
 
+/// The admin may change the further native denoms accepted, the trading fee, where
+/// it is paid, the royalty cap and whether unregistered collections may trade
+#[allow(clippy::too_many_arguments)]
+pub fn execute_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    native_denoms: Option<Vec<String>>,
+    trading_fee_bps: Option<u64>,
+    fee_recipient: Option<String>,
+    max_royalty_bps: Option<u64>,
+    collection_mode: Option<CollectionMode>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info)?;
+    if let Some(native_denoms) = native_denoms {
+        config.native_denoms = native_denoms;
+    }
+    if let Some(trading_fee_bps) = trading_fee_bps {
+        config.trading_fee_bps = trading_fee_bps;
+    }
+    if let Some(fee_recipient) = fee_recipient {
+        config.fee_recipient = validate_addr(deps.api, &fee_recipient)?;
+    }
+    if let Some(max_royalty_bps) = max_royalty_bps {
+        config.max_royalty_bps = max_royalty_bps;
+    }
+    if let Some(collection_mode) = collection_mode {
+        config.collection_mode = collection_mode;
+    }
+    validate_fees(config.trading_fee_bps, config.max_royalty_bps)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "update_config")
+        .add_attribute("trading_fee_bps", config.trading_fee_bps.to_string())
+        .add_attribute("fee_recipient", config.fee_recipient)
+        .add_attribute("max_royalty_bps", config.max_royalty_bps.to_string()))
+}
+
+/// The token info query rejects addresses that are not cw20 contracts
+pub fn execute_add_payment_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info)?;
+    let address = validate_addr(deps.api, &address)?;
+    let token_info: TokenInfoResponse = deps
+        .querier
+        .query_wasm_smart(&address, &Cw20QueryMsg::TokenInfo {})?;
+    let token = PaymentToken {
+        address: address.clone(),
+        name: token_info.name,
+        symbol: token_info.symbol,
+        decimals: token_info.decimals,
+    };
+    PAYMENT_TOKENS.save(deps.storage, address.as_str(), &token)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "add_payment_token")
+        .add_attribute("payment_token", address)
+        .add_attribute("symbol", token.symbol))
+}
+
+pub fn execute_remove_payment_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info)?;
+    let address = validate_addr(deps.api, &address)?;
+    PAYMENT_TOKENS.remove(deps.storage, address.as_str());
+
+    Ok(Response::new()
+        .add_attribute("execute", "remove_payment_token")
+        .add_attribute("payment_token", address))
+}
+
+fn assert_payment_token_allowed(deps: Deps, cw20_contract: &str) -> Result<(), ContractError> {
+    if !PAYMENT_TOKENS.has(deps.storage, cw20_contract) {
+        return Err(ContractError::PaymentTokenNotAllowed {});
+    }
+    Ok(())
+}
+
+pub fn execute_set_collection(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    name: String,
+    verified: bool,
+    blocked: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info)?;
+    let address = validate_addr(deps.api, &address)?;
+    let collection = CollectionInfo {
+        address: address.clone(),
+        name,
+        verified,
+        blocked,
+    };
+    COLLECTIONS.save(deps.storage, address.as_str(), &collection)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "set_collection")
+        .add_attribute("collection", address)
+        .add_attribute("verified", verified.to_string())
+        .add_attribute("blocked", blocked.to_string()))
+}
+
+pub fn execute_remove_collection(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info)?;
+    let address = validate_addr(deps.api, &address)?;
+    COLLECTIONS.remove(deps.storage, address.as_str());
+
+    Ok(Response::new()
+        .add_attribute("execute", "remove_collection")
+        .add_attribute("collection", address))
+}
+
+/// Blocked collections never trade, unregistered ones only when the marketplace is open
+fn assert_collection_allowed(deps: Deps, collection: &str) -> Result<(), ContractError> {
+    let allowed = match COLLECTIONS.may_load(deps.storage, collection)? {
+        Some(info) => !info.blocked,
+        None => CONFIG.load(deps.storage)?.collection_mode == CollectionMode::Open,
+    };
+    if !allowed {
+        return Err(ContractError::CollectionNotAllowed {});
+    }
+    Ok(())
+}
+
+/// The admin may halt kinds of operations, e.g. while a collection is compromised
+pub fn execute_update_pause_status(
+    deps: DepsMut,
+    info: MessageInfo,
+    listing: Option<bool>,
+    purchasing: Option<bool>,
+    bidding: Option<bool>,
+    withdrawals: Option<bool>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info)?;
+    let mut status = PAUSE_STATUS.may_load(deps.storage)?.unwrap_or_default();
+    status.listing = listing.unwrap_or(status.listing);
+    status.purchasing = purchasing.unwrap_or(status.purchasing);
+    status.bidding = bidding.unwrap_or(status.bidding);
+    status.withdrawals = withdrawals.unwrap_or(status.withdrawals);
+    PAUSE_STATUS.save(deps.storage, &status)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "update_pause_status")
+        .add_attribute("listing", status.listing.to_string())
+        .add_attribute("purchasing", status.purchasing.to_string())
+        .add_attribute("bidding", status.bidding.to_string())
+        .add_attribute("withdrawals", status.withdrawals.to_string()))
+}
+
+fn assert_not_paused(
+    deps: Deps,
+    paused: fn(&PauseStatus) -> bool,
+    operation: &str,
+) -> Result<(), ContractError> {
+    let status = PAUSE_STATUS.may_load(deps.storage)?.unwrap_or_default();
+    if paused(&status) {
+        return Err(ContractError::Paused {
+            operation: operation.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Ownership moves in two steps so the marketplace cannot be handed to an address
+/// nobody controls
+pub fn execute_propose_new_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info)?;
+    let admin = validate_addr(deps.api, &admin)?;
+    config.pending_admin = Some(admin.clone());
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "propose_new_admin")
+        .add_attribute("pending_admin", admin))
+}
+
+pub fn execute_accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.pending_admin.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    config.admin = config.pending_admin.take();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "accept_admin")
+        .add_attribute("admin", info.sender))
+}
+
+pub fn execute_renounce_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info)?;
+    config.admin = None;
+    config.pending_admin = None;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("execute", "renounce_admin"))
+}
+
+fn assert_admin(config: &Config, info: &MessageInfo) -> Result<(), ContractError> {
+    if config.admin.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Addresses in messages are validated before they are used or stored
+fn validate_addr(api: &dyn Api, address: &str) -> Result<Addr, ContractError> {
+    api.addr_validate(address)
+        .map_err(|_| ContractError::InvalidAddress {
+            address: address.to_string(),
+        })
+}
+
+/// The trading fee and the largest royalty are both taken out of the sale price
+fn validate_fees(trading_fee_bps: u64, max_royalty_bps: u64) -> Result<(), ContractError> {
+    trading_fee_bps
+        .checked_add(max_royalty_bps)
+        .filter(|bps| *bps <= 10_000)
+        .ok_or(ContractError::InvalidTradingFee {})?;
+    Ok(())
+}
+
+/// The minter of a collection may register the royalty paid on its sales, used when
+/// the collection does not answer cw2981 royalty queries itself
+pub fn execute_set_royalty(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: String,
+    recipient: String,
+    share_bps: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = validate_addr(deps.api, &collection)?;
+    check_collection_minter(deps.as_ref(), &info, &collection)?;
+    if share_bps > config.max_royalty_bps {
+        return Err(ContractError::InvalidRoyalty {
+            max_bps: config.max_royalty_bps,
+        });
+    }
+    let royalty = Royalty {
+        collection: collection.clone(),
+        recipient: validate_addr(deps.api, &recipient)?,
+        share_bps,
+    };
+    ROYALTIES.save(deps.storage, collection.as_str(), &royalty)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "set_royalty")
+        .add_attribute("collection", collection)
+        .add_attribute("recipient", royalty.recipient)
+        .add_attribute("share_bps", share_bps.to_string()))
+}
+
+/// The minter of a collection may remove its registered royalty
+pub fn execute_remove_royalty(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    check_collection_minter(deps.as_ref(), &info, &collection)?;
+    ROYALTIES.remove(deps.storage, collection.as_str());
+
+    Ok(Response::new()
+        .add_attribute("execute", "remove_royalty")
+        .add_attribute("collection", collection))
+}
+
+fn check_collection_minter(
+    deps: Deps,
+    info: &MessageInfo,
+    collection: &Addr,
+) -> Result<(), ContractError> {
+    let minter: MinterResponse = deps
+        .querier
+        .query_wasm_smart(collection, &Cw721BaseQueryMsg::Minter {})?;
+    if info.sender != minter.minter {
+        return Err(ContractError::NotCollectionMinter {});
+    }
+    Ok(())
+}
+
 pub fn receive_cw20(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    match from_binary(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::Purchase {
+    let hook_msg = parse_hook_msg(&cw20_msg.msg)?;
+    match &hook_msg {
+        Cw20HookMsg::Purchase { cw721_contract, .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.purchasing, "purchasing")?;
+            assert_collection_allowed(deps.as_ref(), cw721_contract)?;
+        }
+        Cw20HookMsg::PlaceAuctionBid { collection, .. }
+        | Cw20HookMsg::CommitSealedBid { collection, .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.bidding, "bidding")?;
+            assert_collection_allowed(deps.as_ref(), collection)?;
+        }
+        _ => assert_not_paused(deps.as_ref(), |status| status.bidding, "bidding")?,
+    }
+    match hook_msg {
+        Cw20HookMsg::Purchase {
             cw721_contract,
             token_id,
-        }) => execute_purchase(deps, info, cw721_contract, token_id, cw20_msg),
-        _ => Err(ContractError::CustomError {
-            val: "Invalid Cw20HookMsg".to_string(),
-        }),
+        } => execute_purchase(deps, env, info, cw721_contract, token_id, cw20_msg),
+        Cw20HookMsg::SetBid {
+            collection,
+            token_id,
+            expires,
+        } => execute_set_bid_cw20(deps, env, info, collection, token_id, expires, cw20_msg),
+        Cw20HookMsg::SetCollectionBid {
+            collection,
+            expires,
+        } => execute_set_collection_bid_cw20(deps, env, info, collection, expires, cw20_msg),
+        Cw20HookMsg::SetTraitBid {
+            collection,
+            criteria,
+            expires,
+        } => execute_set_trait_bid_cw20(deps, env, info, collection, criteria, expires, cw20_msg),
+        Cw20HookMsg::PlaceAuctionBid {
+            collection,
+            token_id,
+        } => execute_place_auction_bid_cw20(deps, env, info, collection, token_id, cw20_msg),
+        Cw20HookMsg::CommitSealedBid {
+            collection,
+            token_id,
+            commitment,
+        } => execute_commit_sealed_bid_cw20(
+            deps, env, info, collection, token_id, commitment, cw20_msg,
+        ),
     }
 }
 
 pub fn receive_cw721(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     cw721_msg: Cw721ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    match from_binary(&cw721_msg.msg) {
-        Ok(Cw721HookMsg::SetListing {
-            owner,
-            token_id,
+    let hook_msg = parse_hook_msg(&cw721_msg.msg)?;
+    match &hook_msg {
+        Cw721HookMsg::AcceptCollectionBid { .. } | Cw721HookMsg::AcceptTraitBid { .. } => {
+            assert_not_paused(deps.as_ref(), |status| status.purchasing, "purchasing")?
+        }
+        _ => assert_not_paused(deps.as_ref(), |status| status.listing, "listing")?,
+    }
+    assert_collection_allowed(deps.as_ref(), info.sender.as_str())?;
+    match hook_msg {
+        Cw721HookMsg::SetListing {
             cw20_contract,
+            native_denom,
             amount,
-        }) => execute_set_listing(deps, info, owner, token_id, cw20_contract, amount),
-        _ => Err(ContractError::CustomError {
-            val: "Invalid Cw721HookMsg".to_string(),
-        }),
+            expires_at,
+            payout_address,
+        } => {
+            let payment = listing_denom(deps.as_ref(), cw20_contract, native_denom)?;
+            execute_set_listing(
+                deps,
+                env,
+                info,
+                cw721_msg,
+                payment,
+                amount,
+                None,
+                expires_at,
+                payout_address,
+            )
+        }
+        Cw721HookMsg::SetDutchListing {
+            cw20_contract,
+            native_denom,
+            start_price,
+            floor_price,
+            start_time,
+            end_time,
+            step_secs,
+            expires_at,
+            payout_address,
+        } => {
+            let payment = listing_denom(deps.as_ref(), cw20_contract, native_denom)?;
+            let decline = PriceDecline {
+                floor_price,
+                start_time: start_time.unwrap_or(env.block.time),
+                end_time,
+                step_secs,
+            };
+            execute_set_dutch_listing(
+                deps,
+                env,
+                info,
+                cw721_msg,
+                payment,
+                start_price,
+                decline,
+                expires_at,
+                payout_address,
+            )
+        }
+        Cw721HookMsg::AcceptCollectionBid { bidder } => {
+            execute_accept_collection_bid(deps, env, info, bidder, cw721_msg)
+        }
+        Cw721HookMsg::AcceptTraitBid { id } => {
+            execute_accept_trait_bid(deps, env, info, id, cw721_msg)
+        }
+        Cw721HookMsg::CreateAuction {
+            reserve_price,
+            min_increment_bps,
+            start_time,
+            end_time,
+            extension_secs,
+            payment,
+        } => execute_create_auction(
+            deps,
+            env,
+            info,
+            cw721_msg,
+            reserve_price,
+            min_increment_bps,
+            start_time,
+            end_time,
+            extension_secs,
+            payment,
+        ),
+        Cw721HookMsg::CreateSealedAuction {
+            reserve_price,
+            commit_end,
+            reveal_end,
+            payment,
+        } => execute_create_sealed_auction(
+            deps,
+            env,
+            info,
+            cw721_msg,
+            reserve_price,
+            commit_end,
+            reveal_end,
+            payment,
+        ),
     }
 }
 
+/// Parses the hook of a cw20 or cw721 receive message
+fn parse_hook_msg<T: DeserializeOwned>(msg: &Binary) -> Result<T, ContractError> {
+    from_binary(msg).map_err(|err| ContractError::InvalidHookMsg {
+        reason: err.to_string(),
+    })
+}
+
 pub fn execute_purchase(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     cw721_contract: String,
     token_id: String,
     msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    let cw20_contract = info.sender.to_string();
-    match ASKS.load(deps.storage, (&cw721_contract, &token_id)) {
-        Ok(ask) => {
-            if msg.amount != Uint128::from(ask.price) {
-                return Err(ContractError::CustomError {
-                    val: "Invalid amount".to_string(),
+    let config = CONFIG.load(deps.storage)?;
+    let cw721_contract = validate_addr(deps.api, &cw721_contract)?;
+    let buyer = validate_addr(deps.api, &msg.sender)?;
+    let cw20_contract = info.sender.clone();
+    match asks().may_load(deps.storage, (cw721_contract.as_str(), &token_id))? {
+        Some(ask) => {
+            if ask.is_expired(&env.block) {
+                return Err(ContractError::AskExpired {});
+            }
+            assert_ask_transferable(deps.as_ref(), &env, &ask)?;
+            let denom = Denom::Cw20(info.sender);
+            if ask.payment != denom {
+                return Err(ContractError::WrongPaymentToken {
+                    expected: denom_name(&ask.payment),
+                    received: cw20_contract.into_string(),
                 });
             }
+            let (price, refund) = ask_payment(&ask, env.block.time, msg.amount)?;
 
-            let exe_msg = nft::contract::Cw721ExecuteMsg::TransferNft {
-                recipient: msg.sender,
-                token_id: token_id.clone(),
-            };
-            let wasm_cw721_msg = WasmMsg::Execute {
-                contract_addr: cw721_contract.clone(),
-                msg: to_binary(&exe_msg)?,
-                funds: vec![],
-            };
-
-            let exe_msg = Cw20ExecuteMsg::Transfer {
-                recipient: ask.seller.clone(),
-                amount: ask.price,
-            };
-            let wasm_cw20_msg = WasmMsg::Execute {
-                contract_addr: cw20_contract,
-                msg: to_binary(&exe_msg)?,
-                funds: vec![],
-            };
-
-            CW721_DEPOSITS.remove(deps.storage, (&cw721_contract, &ask.seller, &token_id));
-            ASKS.remove(deps.storage, (&cw721_contract, &token_id));
+            CW721_DEPOSITS.remove(
+                deps.storage,
+                (cw721_contract.as_str(), ask.seller.as_str(), &token_id),
+            );
+            asks().remove(deps.storage, (cw721_contract.as_str(), &token_id))?;
 
-            Ok(Response::new()
+            let res = Response::new()
                 .add_attribute("execute", "purchase")
-                .add_messages(vec![wasm_cw721_msg, wasm_cw20_msg]))
-        }
-        Err(_) => {
-            return Err(ContractError::CustomError {
-                val: "No such ask".to_string(),
-            });
+                .add_message(transfer_nft_msg(&cw721_contract, &token_id, &buyer)?);
+            // pay the seller or their payout address, less the trading fee
+            let mut res = add_sale_payout(
+                deps.as_ref(),
+                res,
+                &config,
+                &denom,
+                &cw721_contract,
+                &token_id,
+                ask.payee(),
+                price,
+            )?;
+            if !refund.is_zero() {
+                res = res.add_message(denom_payment_msg(&denom, &buyer, refund)?);
+            }
+            Ok(res)
         }
+        None => Err(ContractError::AskNotFound {}),
     }
 }
 
 /// A buyer may purchase a listed NFT using native coins
 pub fn execute_purchase_native(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     collection: String,
     token_id: String,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let buyer = info.sender.to_string();
-    let funds_sent = must_pay(&info, &config.native_denom).unwrap();
-    let ask = ASKS.may_load(deps.storage, (&collection, &token_id))?;
+    let collection = validate_addr(deps.api, &collection)?;
+    let buyer = info.sender.clone();
+    let ask = asks().may_load(deps.storage, (collection.as_str(), &token_id))?;
     match ask {
         Some(ask) => {
-            if funds_sent != ask.price {
-                Err(ContractError::CustomError {
-                    val: "Invalid amount".to_string(),
-                })
-            } else {
-                // create message to send payment to seller
-                let payment_msg = BankMsg::Send {
-                    to_address: ask.seller.clone(),
-                    amount: vec![coin(ask.price.u128(), config.native_denom.to_string())],
-                };
-                // create message to transfer nft to buyer
-                let cw721_msg = nft::contract::Cw721ExecuteMsg::TransferNft {
-                    token_id: ask.token_id,
-                    recipient: buyer.clone(),
-                };
-                let wasm_cw721_msg = WasmMsg::Execute {
-                    contract_addr: collection.clone(),
-                    msg: to_binary(&cw721_msg)?,
-                    funds: vec![],
-                };
-                Ok(Response::new()
-                    .add_attribute("execute", "purchase_native")
-                    .add_attribute("collection", collection)
-                    .add_attribute("token_id", token_id)
-                    .add_attribute("buyer", buyer)
-                    .add_attribute("seller", ask.seller)
-                    .add_message(wasm_cw721_msg)
-                    .add_message(payment_msg))
+            if ask.is_expired(&env.block) {
+                return Err(ContractError::AskExpired {});
+            }
+            assert_ask_transferable(deps.as_ref(), &env, &ask)?;
+            let native_denom = match &ask.payment {
+                Denom::Native(native_denom) => native_denom.clone(),
+                Denom::Cw20(cw20_contract) => {
+                    return Err(ContractError::NativePaymentForCw20Ask {
+                        cw20_contract: cw20_contract.to_string(),
+                    })
+                }
+            };
+            let funds_sent = must_pay(&info, &native_denom)?;
+            let (price, refund) = ask_payment(&ask, env.block.time, funds_sent)?;
+
+            CW721_DEPOSITS.remove(
+                deps.storage,
+                (collection.as_str(), ask.seller.as_str(), &token_id),
+            );
+            asks().remove(deps.storage, (collection.as_str(), &token_id))?;
+
+            let res = Response::new()
+                .add_attribute("execute", "purchase_native")
+                .add_attribute("collection", collection.clone())
+                .add_attribute("token_id", token_id.clone())
+                .add_attribute("buyer", buyer.clone())
+                .add_attribute("seller", ask.seller.clone())
+                // transfer the nft to the buyer
+                .add_message(transfer_nft_msg(&collection, &token_id, &buyer)?);
+            // pay the seller or their payout address, less the trading fee
+            let mut res = add_sale_payout(
+                deps.as_ref(),
+                res,
+                &config,
+                &ask.payment,
+                &collection,
+                &token_id,
+                ask.payee(),
+                price,
+            )?;
+            if !refund.is_zero() {
+                res = res.add_message(BankMsg::Send {
+                    to_address: buyer.into_string(),
+                    amount: vec![coin(refund.u128(), native_denom)],
+                });
             }
+            Ok(res)
         }
-        None => Err(ContractError::TokenNotListedForSale {}),
+        None => Err(ContractError::AskNotFound {}),
+    }
+}
+
+/// Splits the amount paid for an ask into the price due at the given time and the
+/// refund. Fixed asks must be paid exactly, while declining asks accept any amount
+/// above the current price so a purchase does not fail as the price moves
+fn ask_payment(
+    ask: &Ask,
+    time: Timestamp,
+    amount: Uint128,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let price = ask.current_price(time);
+    if amount < price || (ask.decline.is_none() && amount != price) {
+        return Err(ContractError::WrongPaymentAmount {
+            expected: price,
+            received: amount,
+        });
     }
+    Ok((price, amount - price))
 }
 
-/// A seller may list their NFT
+/// A seller may list the NFT they sent, the seller being the verified sender of the
+/// cw721 receive message
+#[allow(clippy::too_many_arguments)]
 pub fn execute_set_listing(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    owner: String,
-    token_id: String,
-    cw20_contract: Option<String>,
+    msg: Cw721ReceiveMsg,
+    payment: Denom,
     amount: u128,
+    decline: Option<PriceDecline>,
+    expires_at: Option<Expiration>,
+    payout_address: Option<String>,
 ) -> Result<Response, ContractError> {
-    let collection_contract = info.sender.clone().into_string();
-    //check to see if u
+    let expires_at = expires_at.unwrap_or_default();
+    if expires_at.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let collection_contract = info.sender;
+    let payment = validate_denom(deps.as_ref(), &config, payment)?;
+    let payout_address = payout_address
+        .map(|address| validate_addr(deps.api, &address))
+        .transpose()?;
+    let owner = validate_addr(deps.api, &msg.sender)?;
+    let token_id = msg.token_id;
+    let deposit_key = (
+        collection_contract.as_str(),
+        owner.as_str(),
+        token_id.as_str(),
+    );
 
-    if CW721_DEPOSITS.has(deps.storage, (&collection_contract, &owner, &token_id)) == true {
-        return Err(ContractError::CustomError {
-            val: "Already deposited".to_string(),
-        });
+    if CW721_DEPOSITS.has(deps.storage, deposit_key) {
+        return Err(ContractError::AlreadyListed {});
     }
 
     let deposit = Cw721Deposits {
@@ -254,83 +1123,2392 @@ pub fn execute_set_listing(
         collection: collection_contract.clone(),
         token_id: token_id.clone(),
     };
-    CW721_DEPOSITS
-        .save(
-            deps.storage,
-            (&collection_contract, &owner, &token_id),
-            &deposit,
-        )
-        .unwrap();
+    CW721_DEPOSITS.save(deps.storage, deposit_key, &deposit)?;
 
     let ask = Ask {
         collection: collection_contract.clone(),
         seller: owner.clone(),
         price: Uint128::from(amount),
-        cw20_contract,
+        payment,
         token_id: token_id.clone(),
+        decline,
+        expires_at,
+        payout_address,
     };
 
-    ASKS.save(deps.storage, (&collection_contract, &token_id), &ask)
-        .unwrap();
+    asks().save(
+        deps.storage,
+        (collection_contract.as_str(), &token_id),
+        &ask,
+    )?;
 
     Ok(Response::new()
         .add_attribute("execute", "cw721_deposit")
         .add_attribute("owner", owner)
-        .add_attribute("contract", collection_contract.to_string())
-        .add_attribute("token_id", token_id.to_string()))
+        .add_attribute("contract", collection_contract)
+        .add_attribute("token_id", token_id))
 }
 
-/// A seller may remove their listing of a given NFT
-pub fn execute_remove_listing(
+/// A seller may list their NFT at a declining price
+#[allow(clippy::too_many_arguments)]
+pub fn execute_set_dutch_listing(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    collection: String,
-    token_id: String,
+    msg: Cw721ReceiveMsg,
+    payment: Denom,
+    start_price: Uint128,
+    decline: PriceDecline,
+    expires_at: Option<Expiration>,
+    payout_address: Option<String>,
 ) -> Result<Response, ContractError> {
-    let owner = info.sender.clone().into_string();
-    if CW721_DEPOSITS.has(deps.storage, (&collection, &owner, &token_id)) == false {
-        return Err(ContractError::NoCw721ToWithdraw {});
+    if decline.end_time <= decline.start_time
+        || decline.floor_price >= start_price
+        || decline.step_secs == Some(0)
+    {
+        return Err(ContractError::InvalidPriceDecline {});
     }
+    execute_set_listing(
+        deps,
+        env,
+        info,
+        msg,
+        payment,
+        start_price.u128(),
+        Some(decline),
+        expires_at,
+        payout_address,
+    )
+}
+
+/// A seller may remove their listing of a given NFT, getting a deposited NFT back
+pub fn execute_remove_listing(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let owner = info.sender;
+    let deposit_key = (collection.as_str(), owner.as_str(), token_id.as_str());
+    if !CW721_DEPOSITS.has(deps.storage, deposit_key) {
+        // an ask listed by approval is dropped, its NFT never left the wallet
+        let listed = asks()
+            .may_load(deps.storage, (collection.as_str(), &token_id))?
+            .map_or(false, |ask| ask.seller == owner);
+        if !listed {
+            return Err(ContractError::NoCw721ToWithdraw {});
+        }
+        asks().remove(deps.storage, (collection.as_str(), &token_id))?;
+        return Ok(Response::new()
+            .add_attribute("execute", "remove_ask")
+            .add_attribute("collection", collection)
+            .add_attribute("token_id", token_id));
+    }
+
+    CW721_DEPOSITS.remove(deps.storage, deposit_key);
+    asks().remove(deps.storage, (collection.as_str(), &token_id))?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "withdraw")
+        .add_message(transfer_nft_msg(&collection, &token_id, &owner)?))
+}
+
+/// A seller may list an NFT kept in their wallet, once they have approved the
+/// marketplace to transfer it
+#[allow(clippy::too_many_arguments)]
+pub fn execute_set_ask(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    price: Uint128,
+    payment: Option<Denom>,
+    expires: Option<Expiration>,
+    payout_address: Option<String>,
+) -> Result<Response, ContractError> {
+    let expires_at = expires.unwrap_or_default();
+    if expires_at.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let collection = validate_addr(deps.api, &collection)?;
+    let payment = payment.unwrap_or_else(|| Denom::Native(config.native_denom.clone()));
+    let payment = validate_denom(deps.as_ref(), &config, payment)?;
+    let payout_address = payout_address
+        .map(|address| validate_addr(deps.api, &address))
+        .transpose()?;
+    let seller = info.sender;
+    check_transfer_approval(deps.as_ref(), &env, &collection, &token_id, &seller)?;
+
+    // an ask left by a previous owner of the token is stale and replaced
+    let key = (collection.as_str(), token_id.as_str());
+    if let Some(ask) = asks().may_load(deps.storage, key)? {
+        if ask.seller == seller {
+            return Err(ContractError::AlreadyListed {});
+        }
+    }
+    let ask = Ask {
+        collection: collection.clone(),
+        token_id: token_id.clone(),
+        seller: seller.clone(),
+        price,
+        payment,
+        decline: None,
+        expires_at,
+        payout_address,
+    };
+    asks().save(deps.storage, key, &ask)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "set_ask")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("seller", seller)
+        .add_attribute("price", price))
+}
+
+/// The seller has to own the token and have approved the marketplace to transfer it,
+/// for the token alone or for all their tokens
+fn check_transfer_approval(
+    deps: Deps,
+    env: &Env,
+    collection: &Addr,
+    token_id: &str,
+    seller: &Addr,
+) -> Result<(), ContractError> {
+    let marketplace = &env.contract.address;
+    let owner: OwnerOfResponse = deps.querier.query_wasm_smart(
+        collection,
+        &Cw721QueryMsg::OwnerOf {
+            token_id: token_id.to_string(),
+            include_expired: None,
+        },
+    )?;
+    if *seller != owner.owner {
+        return Err(ContractError::UnauthorizedOwner {});
+    }
+    if owner
+        .approvals
+        .iter()
+        .any(|approval| *marketplace == approval.spender)
+    {
+        return Ok(());
+    }
+    let mut start_after = None;
+    for _ in 0..MAX_OPERATOR_PAGES {
+        let res: OperatorsResponse = deps.querier.query_wasm_smart(
+            collection,
+            &Cw721QueryMsg::AllOperators {
+                owner: seller.to_string(),
+                include_expired: None,
+                start_after,
+                limit: Some(CW721_MAX_QUERY_LIMIT),
+            },
+        )?;
+        if res
+            .operators
+            .iter()
+            .any(|operator| *marketplace == operator.spender)
+        {
+            return Ok(());
+        }
+        if res.operators.len() < CW721_MAX_QUERY_LIMIT as usize {
+            break;
+        }
+        start_after = res
+            .operators
+            .last()
+            .map(|operator| operator.spender.clone());
+    }
+    Err(ContractError::NotApproved {})
+}
+
+/// An ask listed by approval is stale once its token leaves the wallet of the seller
+fn remove_approval_ask(
+    storage: &mut dyn Storage,
+    collection: &Addr,
+    token_id: &str,
+) -> StdResult<()> {
+    let key = (collection.as_str(), token_id);
+    if let Some(ask) = asks().may_load(storage, key)? {
+        let deposit_key = (collection.as_str(), ask.seller.as_str(), token_id);
+        if !CW721_DEPOSITS.has(storage, deposit_key) {
+            asks().remove(storage, key)?;
+        }
+    }
+    Ok(())
+}
+
+/// An ask whose NFT is deposited can always be filled, one listed by approval only
+/// while the seller still owns the token and has it approved
+fn assert_ask_transferable(deps: Deps, env: &Env, ask: &Ask) -> Result<(), ContractError> {
+    if is_deposited(deps, ask) {
+        return Ok(());
+    }
+    check_transfer_approval(deps, env, &ask.collection, &ask.token_id, &ask.seller)
+        .map_err(|_| ContractError::StaleAsk {})
+}
+
+fn is_deposited(deps: Deps, ask: &Ask) -> bool {
+    CW721_DEPOSITS.has(
+        deps.storage,
+        (ask.collection.as_str(), ask.seller.as_str(), &ask.token_id),
+    )
+}
+
+/// Anyone may remove an ask listed by approval that can no longer be filled
+pub fn execute_remove_stale_ask(
+    deps: DepsMut,
+    env: Env,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let ask = asks()
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AskNotFound {})?;
+    if assert_ask_transferable(deps.as_ref(), &env, &ask).is_ok() {
+        return Err(ContractError::AskNotStale {});
+    }
+    asks().remove(deps.storage, (collection.as_str(), &token_id))?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "remove_stale_ask")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("seller", ask.seller))
+}
+
+/// A seller may change the price, currency or expiration of their ask without
+/// withdrawing the NFT
+#[allow(clippy::too_many_arguments)]
+pub fn execute_update_ask(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    price: Option<Uint128>,
+    payment: Option<Denom>,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let mut ask = asks()
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AskNotFound {})?;
+    if info.sender != ask.seller {
+        return Err(ContractError::UnauthorizedOwner {});
+    }
+    // an expired ask can only be revived by giving it a new expiration
+    if expires.is_none() && ask.is_expired(&env.block) {
+        return Err(ContractError::AskExpired {});
+    }
+    if let Some(price) = price {
+        ask.price = price;
+    }
+    if let Some(payment) = payment {
+        let config = CONFIG.load(deps.storage)?;
+        ask.payment = validate_denom(deps.as_ref(), &config, payment)?;
+    }
+    if let Some(expires) = expires {
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::InvalidExpiration {});
+        }
+        ask.expires_at = expires;
+    }
+    if matches!(&ask.decline, Some(decline) if decline.floor_price >= ask.price) {
+        return Err(ContractError::InvalidPriceDecline {});
+    }
+    // saving through the indexed map moves the ask in the payment token and price indexes
+    asks().save(deps.storage, (collection.as_str(), &token_id), &ask)?;
+
+    let event = Event::new("update_ask")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("seller", ask.seller)
+        .add_attribute("price", ask.price)
+        .add_attribute("payment", denom_name(&ask.payment))
+        .add_attribute("expires_at", ask.expires_at.to_string());
+    Ok(Response::new()
+        .add_attribute("execute", "update_ask")
+        .add_event(event))
+}
+
+/// Returns the NFTs of expired asks to their sellers and drops expired asks listed by
+/// approval, oldest expiration first, asks expiring at a height before those at a time
+pub fn execute_return_expired_listings(
+    deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    // an ask is expired once the block reaches its expiration, an empty primary key
+    // sorts before any ask expiring right after the block
+    let expired_before = |now: u64| Bound::exclusive((now + 1, (String::new(), String::new())));
+    let index = &asks().idx.expires_at;
+    let expired = index
+        .sub_prefix(EXPIRES_AT_HEIGHT)
+        .range(
+            deps.storage,
+            None,
+            Some(expired_before(env.block.height)),
+            Order::Ascending,
+        )
+        .chain(index.sub_prefix(EXPIRES_AT_TIME).range(
+            deps.storage,
+            None,
+            Some(expired_before(env.block.time.nanos())),
+            Order::Ascending,
+        ))
+        .take(query_limit(limit))
+        .map(|item| item.map(|(_, ask)| ask))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut res = Response::new()
+        .add_attribute("execute", "return_expired_listings")
+        .add_attribute("returned", expired.len().to_string());
+    for ask in expired {
+        asks().remove(deps.storage, (ask.collection.as_str(), &ask.token_id))?;
+        if is_deposited(deps.as_ref(), &ask) {
+            CW721_DEPOSITS.remove(
+                deps.storage,
+                (ask.collection.as_str(), ask.seller.as_str(), &ask.token_id),
+            );
+            res = res.add_message(transfer_nft_msg(
+                &ask.collection,
+                &ask.token_id,
+                &ask.seller,
+            )?);
+        }
+    }
+    Ok(res)
+}
+
+/// A buyer may bid native coins on a given NFT, escrowing the funds sent
+pub fn execute_set_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = validate_addr(deps.api, &collection)?;
+    let price = must_pay(&info, &config.native_denom)?;
+    let payment = Denom::Native(config.native_denom);
+    set_bid(
+        deps,
+        env,
+        collection,
+        token_id,
+        info.sender,
+        price,
+        payment,
+        expires,
+    )
+}
+
+/// A buyer may bid cw20 tokens on a given NFT, escrowing the tokens sent
+pub fn execute_set_bid_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    expires: Option<Expiration>,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = validate_addr(deps.api, &msg.sender)?;
+    let payment = Denom::Cw20(info.sender);
+    set_bid(
+        deps, env, collection, token_id, bidder, msg.amount, payment, expires,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn set_bid(
+    deps: DepsMut,
+    env: Env,
+    collection: Addr,
+    token_id: String,
+    bidder: Addr,
+    price: Uint128,
+    payment: Denom,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+
+    // a new bid replaces the previous one, whose escrow is refunded
+    let key = (collection.as_str(), token_id.as_str(), bidder.as_str());
+    let mut res = Response::new();
+    if let Some(previous) = bids().may_load(deps.storage, key)? {
+        res = res.add_message(denom_payment_msg(
+            &previous.payment,
+            &bidder,
+            previous.price,
+        )?);
+    }
+
+    let bid = Bid {
+        collection: collection.clone(),
+        token_id: token_id.clone(),
+        bidder: bidder.clone(),
+        price,
+        payment: payment.clone(),
+        expires,
+    };
+    bids().save(deps.storage, key, &bid)?;
+
+    Ok(res
+        .add_attribute("execute", "set_bid")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("bidder", bidder)
+        .add_attribute("price", price)
+        .add_attribute("payment_token", denom_name(&payment)))
+}
+
+/// A bidder may remove their bid and get the escrow back
+pub fn execute_remove_bid(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = info.sender;
+    let key = (collection.as_str(), token_id.as_str(), bidder.as_str());
+    let bid = bids()
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::BidNotFound {})?;
+    bids().remove(deps.storage, key)?;
+
+    let refund_msg = denom_payment_msg(&bid.payment, &bidder, bid.price)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "remove_bid")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", token_id)
+        .add_attribute("bidder", bidder)
+        .add_message(refund_msg))
+}
+
+/// The owner of an NFT may sell it to a bidder. A token held by the marketplace
+/// is released from escrow, any other token is transferred on behalf of its owner,
+/// who must have approved the marketplace beforehand.
+pub fn execute_accept_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    bidder: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = validate_addr(deps.api, &bidder)?;
+    let seller = info.sender;
+    let bid_key = (collection.as_str(), token_id.as_str(), bidder.as_str());
+    let bid = bids()
+        .may_load(deps.storage, bid_key)?
+        .ok_or(ContractError::BidNotFound {})?;
+    if bid.expires.is_expired(&env.block) {
+        return Err(ContractError::BidExpired {});
+    }
+
+    let deposit_key = (collection.as_str(), seller.as_str(), token_id.as_str());
+    if CW721_DEPOSITS.has(deps.storage, deposit_key) {
+        CW721_DEPOSITS.remove(deps.storage, deposit_key);
+    } else {
+        let owner: OwnerOfResponse = deps.querier.query_wasm_smart(
+            &collection,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: token_id.clone(),
+                include_expired: None,
+            },
+        )?;
+        if seller != owner.owner {
+            return Err(ContractError::UnauthorizedOwner {});
+        }
+    }
+    // an ask of the seller on the token, deposited or listed by approval, is filled too
+    asks().remove(deps.storage, (collection.as_str(), &token_id))?;
+    bids().remove(deps.storage, bid_key)?;
+
+    // release escrow to seller, less the trading fee
+    let res = Response::new()
+        .add_attribute("execute", "accept_bid")
+        .add_attribute("collection", collection.clone())
+        .add_attribute("token_id", token_id.clone())
+        .add_attribute("bidder", bidder.clone())
+        .add_attribute("seller", seller.clone())
+        // transfer the nft to the bidder
+        .add_message(transfer_nft_msg(&collection, &token_id, &bidder)?);
+    Ok(add_sale_payout(
+        deps.as_ref(),
+        res,
+        &config,
+        &bid.payment,
+        &collection,
+        &token_id,
+        &seller,
+        bid.price,
+    )?)
+}
+
+/// A buyer may bid native coins on any NFT of a collection, escrowing the funds sent
+pub fn execute_set_collection_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = validate_addr(deps.api, &collection)?;
+    let price = must_pay(&info, &config.native_denom)?;
+    let payment = Denom::Native(config.native_denom);
+    set_collection_bid(deps, env, collection, info.sender, price, payment, expires)
+}
+
+/// A buyer may bid cw20 tokens on any NFT of a collection, escrowing the tokens sent
+pub fn execute_set_collection_bid_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    expires: Option<Expiration>,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = validate_addr(deps.api, &msg.sender)?;
+    let payment = Denom::Cw20(info.sender);
+    set_collection_bid(deps, env, collection, bidder, msg.amount, payment, expires)
+}
+
+fn set_collection_bid(
+    deps: DepsMut,
+    env: Env,
+    collection: Addr,
+    bidder: Addr,
+    price: Uint128,
+    payment: Denom,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+
+    // a new bid replaces the previous one, whose escrow is refunded
+    let key = (collection.as_str(), bidder.as_str());
+    let mut res = Response::new();
+    if let Some(previous) = collection_bids().may_load(deps.storage, key)? {
+        res = res.add_message(denom_payment_msg(
+            &previous.payment,
+            &bidder,
+            previous.price,
+        )?);
+    }
+
+    let bid = CollectionBid {
+        collection: collection.clone(),
+        bidder: bidder.clone(),
+        price,
+        payment: payment.clone(),
+        expires,
+    };
+    collection_bids().save(deps.storage, key, &bid)?;
+
+    Ok(res
+        .add_attribute("execute", "set_collection_bid")
+        .add_attribute("collection", collection)
+        .add_attribute("bidder", bidder)
+        .add_attribute("price", price)
+        .add_attribute("payment_token", denom_name(&payment)))
+}
+
+/// A bidder may remove their collection bid and get the escrow back
+pub fn execute_remove_collection_bid(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = info.sender;
+    let key = (collection.as_str(), bidder.as_str());
+    let bid = collection_bids()
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::BidNotFound {})?;
+    collection_bids().remove(deps.storage, key)?;
+
+    let refund_msg = denom_payment_msg(&bid.payment, &bidder, bid.price)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "remove_collection_bid")
+        .add_attribute("collection", collection)
+        .add_attribute("bidder", bidder)
+        .add_message(refund_msg))
+}
+
+/// A holder fills a collection bid by sending any NFT of that collection
+pub fn execute_accept_collection_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    bidder: String,
+    msg: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = info.sender;
+    let seller = validate_addr(deps.api, &msg.sender)?;
+    let bidder = validate_addr(deps.api, &bidder)?;
+    let key = (collection.as_str(), bidder.as_str());
+    let bid = collection_bids()
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::BidNotFound {})?;
+    if bid.expires.is_expired(&env.block) {
+        return Err(ContractError::BidExpired {});
+    }
+    collection_bids().remove(deps.storage, key)?;
+    remove_approval_ask(deps.storage, &collection, &msg.token_id)?;
+
+    // release escrow to seller, less the trading fee
+    let res = Response::new()
+        .add_attribute("execute", "accept_collection_bid")
+        .add_attribute("collection", collection.clone())
+        .add_attribute("token_id", msg.token_id.clone())
+        .add_attribute("bidder", bidder.clone())
+        .add_attribute("seller", seller.clone())
+        // transfer the nft to the bidder
+        .add_message(transfer_nft_msg(&collection, &msg.token_id, &bidder)?);
+    Ok(add_sale_payout(
+        deps.as_ref(),
+        res,
+        &config,
+        &bid.payment,
+        &collection,
+        &msg.token_id,
+        &seller,
+        bid.price,
+    )?)
+}
+
+/// A buyer may bid native coins on any NFT of a collection matching the metadata criteria,
+/// escrowing the funds sent
+pub fn execute_set_trait_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    criteria: MetadataCriteria,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = validate_addr(deps.api, &collection)?;
+    let price = must_pay(&info, &config.native_denom)?;
+    let payment = Denom::Native(config.native_denom);
+    set_trait_bid(
+        deps,
+        env,
+        collection,
+        info.sender,
+        price,
+        payment,
+        criteria,
+        expires,
+    )
+}
+
+/// A buyer may bid cw20 tokens on any NFT of a collection matching the metadata criteria,
+/// escrowing the tokens sent
+pub fn execute_set_trait_bid_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    criteria: MetadataCriteria,
+    expires: Option<Expiration>,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = validate_addr(deps.api, &msg.sender)?;
+    let payment = Denom::Cw20(info.sender);
+    set_trait_bid(
+        deps, env, collection, bidder, msg.amount, payment, criteria, expires,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn set_trait_bid(
+    deps: DepsMut,
+    env: Env,
+    collection: Addr,
+    bidder: Addr,
+    price: Uint128,
+    payment: Denom,
+    criteria: MetadataCriteria,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+
+    let id = TRAIT_BID_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    TRAIT_BID_COUNT.save(deps.storage, &id)?;
+    let bid = TraitBid {
+        id,
+        collection: collection.clone(),
+        bidder: bidder.clone(),
+        price,
+        payment: payment.clone(),
+        criteria,
+        expires,
+    };
+    trait_bids().save(deps.storage, id, &bid)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "set_trait_bid")
+        .add_attribute("id", id.to_string())
+        .add_attribute("collection", collection)
+        .add_attribute("bidder", bidder)
+        .add_attribute("price", price)
+        .add_attribute("payment_token", denom_name(&payment)))
+}
+
+/// A bidder may remove their trait bid and get the escrow back
+pub fn execute_remove_trait_bid(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let bid = trait_bids()
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::BidNotFound {})?;
+    if bid.bidder != info.sender {
+        return Err(ContractError::BidNotFound {});
+    }
+    trait_bids().remove(deps.storage, id)?;
+
+    let refund_msg = denom_payment_msg(&bid.payment, &bid.bidder, bid.price)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "remove_trait_bid")
+        .add_attribute("id", id.to_string())
+        .add_attribute("bidder", bid.bidder)
+        .add_message(refund_msg))
+}
+
+/// A holder fills a trait bid by sending an NFT of that collection, whose on-chain
+/// metadata is checked against the bid criteria
+pub fn execute_accept_trait_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    msg: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = info.sender;
+    let seller = validate_addr(deps.api, &msg.sender)?;
+    let bid = trait_bids()
+        .may_load(deps.storage, id)?
+        .filter(|bid| bid.collection == collection)
+        .ok_or(ContractError::BidNotFound {})?;
+    if bid.expires.is_expired(&env.block) {
+        return Err(ContractError::BidExpired {});
+    }
+
+    let nft_info: AllNftInfoResponse<Extension> = deps.querier.query_wasm_smart(
+        &collection,
+        &Cw721QueryMsg::AllNftInfo {
+            token_id: msg.token_id.clone(),
+            include_expired: None,
+        },
+    )?;
+    match nft_info.info.extension {
+        Some(metadata) if bid.criteria.matches(&metadata) => {}
+        _ => return Err(ContractError::CriteriaNotMet {}),
+    }
+    trait_bids().remove(deps.storage, id)?;
+    remove_approval_ask(deps.storage, &collection, &msg.token_id)?;
+
+    // release escrow to seller, less the trading fee
+    let res = Response::new()
+        .add_attribute("execute", "accept_trait_bid")
+        .add_attribute("id", id.to_string())
+        .add_attribute("collection", collection.clone())
+        .add_attribute("token_id", msg.token_id.clone())
+        .add_attribute("bidder", bid.bidder.clone())
+        .add_attribute("seller", seller.clone())
+        // transfer the nft to the bidder
+        .add_message(transfer_nft_msg(&collection, &msg.token_id, &bid.bidder)?);
+    Ok(add_sale_payout(
+        deps.as_ref(),
+        res,
+        &config,
+        &bid.payment,
+        &collection,
+        &msg.token_id,
+        &seller,
+        bid.price,
+    )?)
+}
+
+/// A seller may auction the NFT sent to the marketplace
+#[allow(clippy::too_many_arguments)]
+pub fn execute_create_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw721ReceiveMsg,
+    reserve_price: Uint128,
+    min_increment_bps: u64,
+    start_time: Option<Timestamp>,
+    end_time: Timestamp,
+    extension_secs: u64,
+    payment: Denom,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = info.sender;
+    let seller = validate_addr(deps.api, &msg.sender)?;
+    let start_time = start_time.unwrap_or(env.block.time);
+    if end_time <= start_time || end_time <= env.block.time {
+        return Err(ContractError::InvalidAuctionTimes {});
+    }
+    if min_increment_bps > 10_000 {
+        return Err(ContractError::InvalidIncrement {});
+    }
+    let payment = validate_denom(deps.as_ref(), &config, payment)?;
+    let key = (collection.as_str(), msg.token_id.as_str());
+    if auctions().may_load(deps.storage, key)?.is_some() {
+        return Err(ContractError::AlreadyListed {});
+    }
+
+    let auction = Auction {
+        collection: collection.clone(),
+        token_id: msg.token_id.clone(),
+        seller: seller.clone(),
+        payment,
+        reserve_price,
+        min_increment_bps,
+        start_time,
+        end_time,
+        extension_secs,
+        highest_bid: None,
+    };
+    auctions().save(deps.storage, key, &auction)?;
+    remove_approval_ask(deps.storage, &collection, &msg.token_id)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "create_auction")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", msg.token_id)
+        .add_attribute("seller", seller)
+        .add_attribute("reserve_price", reserve_price)
+        .add_attribute("end_time", end_time.to_string()))
+}
+
+/// A bidder may outbid the highest bid of a native auction
+pub fn execute_place_auction_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let auction = auctions()
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    let amount = match &auction.payment {
+        Denom::Native(denom) => must_pay(&info, denom)?,
+        Denom::Cw20(cw20_contract) => {
+            return Err(ContractError::NativePaymentForCw20Ask {
+                cw20_contract: cw20_contract.to_string(),
+            })
+        }
+    };
+    place_auction_bid(deps, env, auction, info.sender, amount)
+}
+
+/// A bidder may outbid the highest bid of a cw20 auction
+pub fn execute_place_auction_bid_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = validate_addr(deps.api, &msg.sender)?;
+    let auction = auctions()
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    if auction.payment != Denom::Cw20(info.sender.clone()) {
+        return Err(ContractError::WrongPaymentToken {
+            expected: denom_name(&auction.payment),
+            received: info.sender.into_string(),
+        });
+    }
+    place_auction_bid(deps, env, auction, bidder, msg.amount)
+}
+
+fn place_auction_bid(
+    deps: DepsMut,
+    env: Env,
+    mut auction: Auction,
+    bidder: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let now = env.block.time;
+    if now < auction.start_time {
+        return Err(ContractError::AuctionNotStarted {});
+    }
+    if now >= auction.end_time {
+        return Err(ContractError::AuctionEnded {});
+    }
+    if bidder == auction.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    let min = min_auction_bid(&auction);
+    if amount < min {
+        return Err(ContractError::BidTooLow { min });
+    }
+
+    // the previous highest bid is refunded
+    let mut res = Response::new();
+    let bid = AuctionBid {
+        bidder: bidder.clone(),
+        amount,
+    };
+    if let Some(previous) = auction.highest_bid.replace(bid) {
+        res = res.add_message(denom_payment_msg(
+            &auction.payment,
+            &previous.bidder,
+            previous.amount,
+        )?);
+    }
+
+    // a bid close to the end extends the auction to stop sniping
+    let extended_end = now.plus_seconds(auction.extension_secs);
+    if extended_end > auction.end_time {
+        auction.end_time = extended_end;
+    }
+    auctions().save(
+        deps.storage,
+        (auction.collection.as_str(), &auction.token_id),
+        &auction,
+    )?;
+
+    Ok(res
+        .add_attribute("execute", "place_auction_bid")
+        .add_attribute("collection", auction.collection)
+        .add_attribute("token_id", auction.token_id)
+        .add_attribute("bidder", bidder)
+        .add_attribute("amount", amount)
+        .add_attribute("end_time", auction.end_time.to_string()))
+}
+
+/// Smallest bid accepted next: any amount opens the auction, later bids must raise
+/// the highest bid by `min_increment_bps`, and always by at least one unit
+pub fn min_auction_bid(auction: &Auction) -> Uint128 {
+    match &auction.highest_bid {
+        None => Uint128::new(1),
+        Some(bid) => {
+            let increment = bid
+                .amount
+                .multiply_ratio(auction.min_increment_bps, 10_000u128);
+            bid.amount + increment.max(Uint128::new(1))
+        }
+    }
+}
+
+/// Anyone may settle an auction once it has ended
+pub fn execute_settle_auction(
+    deps: DepsMut,
+    env: Env,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let auction = auctions()
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    if env.block.time < auction.end_time {
+        return Err(ContractError::AuctionNotEnded {});
+    }
+    auctions().remove(deps.storage, (collection.as_str(), &token_id))?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let res = Response::new()
+        .add_attribute("execute", "settle_auction")
+        .add_attribute("collection", collection.clone())
+        .add_attribute("token_id", token_id.clone());
+    match auction.highest_bid {
+        Some(bid) if bid.amount >= auction.reserve_price => {
+            let res = res
+                .add_attribute("winner", bid.bidder.clone())
+                .add_message(transfer_nft_msg(&collection, &token_id, &bid.bidder)?);
+            Ok(add_sale_payout(
+                deps.as_ref(),
+                res,
+                &config,
+                &auction.payment,
+                &collection,
+                &token_id,
+                &auction.seller,
+                bid.amount,
+            )?)
+        }
+        highest_bid => {
+            // reserve not met, the NFT goes back to the seller and the bid is refunded
+            let mut res = res
+                .add_attribute("reserve_met", "false")
+                .add_message(transfer_nft_msg(&collection, &token_id, &auction.seller)?);
+            if let Some(bid) = highest_bid {
+                res = res.add_message(denom_payment_msg(
+                    &auction.payment,
+                    &bid.bidder,
+                    bid.amount,
+                )?);
+            }
+            Ok(res)
+        }
+    }
+}
+
+/// A seller may put the NFT sent to the marketplace up for a sealed-bid auction
+#[allow(clippy::too_many_arguments)]
+pub fn execute_create_sealed_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw721ReceiveMsg,
+    reserve_price: Uint128,
+    commit_end: Timestamp,
+    reveal_end: Timestamp,
+    payment: Denom,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let collection = info.sender;
+    let seller = validate_addr(deps.api, &msg.sender)?;
+    if commit_end <= env.block.time || reveal_end <= commit_end {
+        return Err(ContractError::InvalidAuctionTimes {});
+    }
+    let payment = validate_denom(deps.as_ref(), &config, payment)?;
+    let key = (collection.as_str(), msg.token_id.as_str());
+    if SEALED_AUCTIONS.may_load(deps.storage, key)?.is_some() {
+        return Err(ContractError::AlreadyListed {});
+    }
+
+    let auction = SealedAuction {
+        collection: collection.clone(),
+        token_id: msg.token_id.clone(),
+        seller: seller.clone(),
+        payment,
+        reserve_price,
+        commit_end,
+        reveal_end,
+        highest_bid: None,
+        bid_count: 0,
+    };
+    SEALED_AUCTIONS.save(deps.storage, key, &auction)?;
+    remove_approval_ask(deps.storage, &collection, &msg.token_id)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "create_sealed_auction")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", msg.token_id)
+        .add_attribute("seller", seller)
+        .add_attribute("commit_end", commit_end.to_string())
+        .add_attribute("reveal_end", reveal_end.to_string()))
+}
+
+/// A bidder may commit to a sealed bid of a native auction, the coins sent are collateral
+pub fn execute_commit_sealed_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    commitment: Binary,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let auction = SEALED_AUCTIONS
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    let collateral = match &auction.payment {
+        Denom::Native(denom) => must_pay(&info, denom)?,
+        Denom::Cw20(cw20_contract) => {
+            return Err(ContractError::NativePaymentForCw20Ask {
+                cw20_contract: cw20_contract.to_string(),
+            })
+        }
+    };
+    commit_sealed_bid(deps, env, auction, info.sender, commitment, collateral)
+}
+
+/// A bidder may commit to a sealed bid of a cw20 auction, the tokens sent are collateral
+pub fn execute_commit_sealed_bid_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    commitment: Binary,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = validate_addr(deps.api, &msg.sender)?;
+    let auction = SEALED_AUCTIONS
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    if auction.payment != Denom::Cw20(info.sender.clone()) {
+        return Err(ContractError::WrongPaymentToken {
+            expected: denom_name(&auction.payment),
+            received: info.sender.into_string(),
+        });
+    }
+    commit_sealed_bid(deps, env, auction, bidder, commitment, msg.amount)
+}
+
+fn commit_sealed_bid(
+    deps: DepsMut,
+    env: Env,
+    mut auction: SealedAuction,
+    bidder: Addr,
+    commitment: Binary,
+    collateral: Uint128,
+) -> Result<Response, ContractError> {
+    if env.block.time >= auction.commit_end {
+        return Err(ContractError::CommitPhaseEnded {});
+    }
+    if bidder == auction.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    // collateral below the reserve can never back a winning bid
+    if collateral < auction.reserve_price {
+        return Err(ContractError::BidTooLow {
+            min: auction.reserve_price,
+        });
+    }
+    if auction.bid_count >= MAX_SEALED_BIDS {
+        return Err(ContractError::TooManySealedBids {
+            max: MAX_SEALED_BIDS,
+        });
+    }
+    let key = (
+        auction.collection.as_str(),
+        auction.token_id.as_str(),
+        bidder.as_str(),
+    );
+    if SEALED_BIDS.has(deps.storage, key) {
+        return Err(ContractError::AlreadyCommitted {});
+    }
+    let bid = SealedBid {
+        bidder: bidder.clone(),
+        commitment,
+        collateral,
+        revealed: None,
+    };
+    SEALED_BIDS.save(deps.storage, key, &bid)?;
+    auction.bid_count += 1;
+    SEALED_AUCTIONS.save(
+        deps.storage,
+        (auction.collection.as_str(), &auction.token_id),
+        &auction,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "commit_sealed_bid")
+        .add_attribute("collection", auction.collection)
+        .add_attribute("token_id", auction.token_id)
+        .add_attribute("bidder", bidder)
+        .add_attribute("collateral", collateral))
+}
+
+/// Commitment a bidder submits to bid `amount` on the sealed-bid auction of a token,
+/// bound to the token so a commitment cannot be replayed on another auction
+pub fn sealed_bid_commitment(
+    collection: &str,
+    token_id: &str,
+    bidder: &str,
+    amount: Uint128,
+    salt: &str,
+) -> Binary {
+    let preimage = format!("{}:{}:{}:{}:{}", collection, token_id, bidder, amount, salt);
+    Binary::from(Sha256::digest(preimage.as_bytes()).to_vec())
+}
+
+/// A bidder may reveal their sealed bid. The highest reveal is kept, any reveal it
+/// beats or that falls short is refunded right away
+pub fn execute_reveal_sealed_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    amount: Uint128,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let bidder = info.sender;
+    let mut auction = SEALED_AUCTIONS
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    let now = env.block.time;
+    if now < auction.commit_end || now >= auction.reveal_end {
+        return Err(ContractError::NotRevealPhase {});
+    }
+    let key = (collection.as_str(), token_id.as_str(), bidder.as_str());
+    let mut bid = SEALED_BIDS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::BidNotFound {})?;
+    if bid.revealed.is_some() {
+        return Err(ContractError::AlreadyRevealed {});
+    }
+    let commitment = sealed_bid_commitment(
+        collection.as_str(),
+        &token_id,
+        bidder.as_str(),
+        amount,
+        &salt,
+    );
+    if bid.commitment != commitment {
+        return Err(ContractError::InvalidReveal {});
+    }
+    if amount > bid.collateral {
+        return Err(ContractError::CollateralTooLow {
+            collateral: bid.collateral,
+        });
+    }
+
+    let res = Response::new()
+        .add_attribute("execute", "reveal_sealed_bid")
+        .add_attribute("collection", collection.clone())
+        .add_attribute("token_id", token_id.clone())
+        .add_attribute("bidder", bidder.clone())
+        .add_attribute("amount", amount);
+    let leads = amount >= auction.reserve_price
+        && auction
+            .highest_bid
+            .as_ref()
+            .map_or(true, |highest| amount > highest.amount);
+    if !leads {
+        SEALED_BIDS.remove(deps.storage, key);
+        return Ok(res.add_message(denom_payment_msg(
+            &auction.payment,
+            &bidder,
+            bid.collateral,
+        )?));
+    }
+
+    let mut res = res;
+    let highest = AuctionBid {
+        bidder: bidder.clone(),
+        amount,
+    };
+    if let Some(previous) = auction.highest_bid.replace(highest) {
+        let previous_key = (
+            collection.as_str(),
+            token_id.as_str(),
+            previous.bidder.as_str(),
+        );
+        let previous_bid = SEALED_BIDS.load(deps.storage, previous_key)?;
+        SEALED_BIDS.remove(deps.storage, previous_key);
+        res = res.add_message(denom_payment_msg(
+            &auction.payment,
+            &previous.bidder,
+            previous_bid.collateral,
+        )?);
+    }
+    bid.revealed = Some(amount);
+    SEALED_BIDS.save(deps.storage, key, &bid)?;
+    SEALED_AUCTIONS.save(deps.storage, (collection.as_str(), &token_id), &auction)?;
+
+    Ok(res)
+}
+
+/// Anyone may settle a sealed-bid auction once its reveal phase is over
+pub fn execute_settle_sealed_auction(
+    deps: DepsMut,
+    env: Env,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let collection = validate_addr(deps.api, &collection)?;
+    let auction = SEALED_AUCTIONS
+        .may_load(deps.storage, (collection.as_str(), &token_id))?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    if env.block.time < auction.reveal_end {
+        return Err(ContractError::AuctionNotEnded {});
+    }
+    let bids = SEALED_BIDS
+        .prefix((collection.as_str(), &token_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<_>>>()?;
+    for bid in &bids {
+        SEALED_BIDS.remove(
+            deps.storage,
+            (collection.as_str(), &token_id, bid.bidder.as_str()),
+        );
+    }
+    SEALED_AUCTIONS.remove(deps.storage, (collection.as_str(), &token_id));
+
+    // only the leading bid is left revealed, the collateral of the others is slashed
+    let config = CONFIG.load(deps.storage)?;
+    let mut slashed = Uint128::zero();
+    let mut res = Response::new()
+        .add_attribute("execute", "settle_sealed_auction")
+        .add_attribute("collection", collection.clone())
+        .add_attribute("token_id", token_id.clone());
+    for bid in bids {
+        match bid.revealed {
+            Some(amount) => {
+                let change = bid.collateral - amount;
+                if !change.is_zero() {
+                    res =
+                        res.add_message(denom_payment_msg(&auction.payment, &bid.bidder, change)?);
+                }
+            }
+            None => slashed += bid.collateral,
+        }
+    }
+    match &auction.highest_bid {
+        Some(highest) => {
+            res = res
+                .add_attribute("winner", highest.bidder.clone())
+                .add_message(transfer_nft_msg(&collection, &token_id, &highest.bidder)?);
+            res = add_sale_payout(
+                deps.as_ref(),
+                res,
+                &config,
+                &auction.payment,
+                &collection,
+                &token_id,
+                &auction.seller,
+                highest.amount,
+            )?;
+        }
+        None => {
+            res = res.add_message(transfer_nft_msg(&collection, &token_id, &auction.seller)?);
+        }
+    }
+    if !slashed.is_zero() {
+        res = res
+            .add_attribute("slashed", slashed)
+            .add_message(denom_payment_msg(
+                &auction.payment,
+                &auction.seller,
+                slashed,
+            )?);
+    }
+    Ok(res)
+}
+
+/// Denom a listing is priced in, the default native denom unless one is given
+fn listing_denom(
+    deps: Deps,
+    cw20_contract: Option<String>,
+    native_denom: Option<String>,
+) -> Result<Denom, ContractError> {
+    match (cw20_contract, native_denom) {
+        (Some(_), Some(_)) => Err(ContractError::AmbiguousPayment {}),
+        (Some(cw20_contract), None) => Ok(Denom::Cw20(validate_addr(deps.api, &cw20_contract)?)),
+        (None, Some(native_denom)) => Ok(Denom::Native(native_denom)),
+        (None, None) => Ok(Denom::Native(CONFIG.load(deps.storage)?.native_denom)),
+    }
+}
+
+/// Only the native denoms and cw20 tokens accepted by the admin can be used for payment.
+/// A cw20 address deserialized from a message is unchecked, so it is validated here
+fn validate_denom(deps: Deps, config: &Config, denom: Denom) -> Result<Denom, ContractError> {
+    match denom {
+        Denom::Native(denom) if config.accepts_native_denom(&denom) => Ok(Denom::Native(denom)),
+        Denom::Native(denom) => Err(ContractError::DenomNotAllowed { denom }),
+        Denom::Cw20(cw20_contract) => {
+            let cw20_contract = validate_addr(deps.api, cw20_contract.as_str())?;
+            assert_payment_token_allowed(deps, cw20_contract.as_str())?;
+            Ok(Denom::Cw20(cw20_contract))
+        }
+    }
+}
+
+/// Native denom or cw20 address, as shown in errors
+fn denom_name(denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => denom.clone(),
+        Denom::Cw20(cw20_contract) => cw20_contract.to_string(),
+    }
+}
 
-    CW721_DEPOSITS.remove(deps.storage, (&collection, &owner, &token_id));
-    ASKS.remove(deps.storage, (&collection, &token_id));
+/// Pays `amount` out of the marketplace balance in the given denom
+fn denom_payment_msg(denom: &Denom, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match denom {
+        Denom::Cw20(cw20_contract) => WasmMsg::Execute {
+            contract_addr: cw20_contract.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+        Denom::Native(denom) => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![coin(amount.u128(), denom)],
+        }
+        .into(),
+    })
+}
 
-    let exe_msg = nft::contract::Cw721ExecuteMsg::TransferNft {
-        recipient: owner,
-        token_id: token_id,
+fn transfer_nft_msg(collection: &Addr, token_id: &str, recipient: &Addr) -> StdResult<CosmosMsg> {
+    let cw721_msg = nft::contract::Cw721ExecuteMsg::TransferNft {
+        recipient: recipient.to_string(),
+        token_id: token_id.to_string(),
     };
-    let msg = WasmMsg::Execute {
-        contract_addr: collection,
-        msg: to_binary(&exe_msg)?,
+    Ok(WasmMsg::Execute {
+        contract_addr: collection.to_string(),
+        msg: to_binary(&cw721_msg)?,
         funds: vec![],
+    }
+    .into())
+}
+
+/// Pays the proceeds of a sale to the seller, the royalty to the creator and the
+/// trading fee to the fee recipient
+#[allow(clippy::too_many_arguments)]
+fn add_sale_payout(
+    deps: Deps,
+    res: Response,
+    config: &Config,
+    denom: &Denom,
+    collection: &Addr,
+    token_id: &str,
+    seller: &Addr,
+    price: Uint128,
+) -> StdResult<Response> {
+    let fee = price.multiply_ratio(config.trading_fee_bps, 10_000u128);
+    let royalty = resolve_royalty(deps, config, collection, token_id, price)?;
+    let royalty_amount = royalty
+        .as_ref()
+        .map(|(_, amount)| *amount)
+        .unwrap_or_default();
+    let seller_amount = price - fee - royalty_amount;
+    let mut res = res
+        .add_attribute("price", price)
+        .add_attribute("fee", fee)
+        .add_attribute("royalty", royalty_amount)
+        .add_attribute("seller_amount", seller_amount);
+    if !seller_amount.is_zero() {
+        res = res.add_message(denom_payment_msg(denom, seller, seller_amount)?);
+    }
+    if !fee.is_zero() {
+        res = res.add_message(denom_payment_msg(denom, &config.fee_recipient, fee)?);
+    }
+    if let Some((recipient, amount)) = royalty {
+        res = res
+            .add_attribute("royalty_recipient", recipient.clone())
+            .add_message(denom_payment_msg(denom, &recipient, amount)?);
+    }
+    Ok(res)
+}
+
+/// Royalty owed on a sale, from the collection if it answers the cw2981 royalty query
+/// with a royalty for the token and from the marketplace registry otherwise, capped at
+/// `max_royalty_bps` of the price
+fn resolve_royalty(
+    deps: Deps,
+    config: &Config,
+    collection: &Addr,
+    token_id: &str,
+    price: Uint128,
+) -> StdResult<Option<(Addr, Uint128)>> {
+    let query = nft::msg::QueryMsg::RoyaltyInfo {
+        token_id: token_id.to_string(),
+        sale_price: price,
+    };
+    // a royalty address the collection answers with is only paid if it is valid
+    let from_collection = deps
+        .querier
+        .query_wasm_smart::<nft::msg::RoyaltiesInfoResponse>(collection, &query)
+        .ok()
+        .filter(|info| !info.royalty_amount.is_zero())
+        .and_then(|info| {
+            let recipient = deps.api.addr_validate(&info.address).ok()?;
+            Some((recipient, info.royalty_amount))
+        });
+    let royalty = match from_collection {
+        Some(royalty) => Some(royalty),
+        None => ROYALTIES
+            .may_load(deps.storage, collection.as_str())?
+            .map(|royalty| {
+                let amount = price.multiply_ratio(royalty.share_bps, 10_000u128);
+                (royalty.recipient, amount)
+            }),
     };
+    let cap = price.multiply_ratio(config.max_royalty_bps, 10_000u128);
+    Ok(royalty
+        .map(|(recipient, amount)| (recipient, amount.min(cap)))
+        .filter(|(_, amount)| !amount.is_zero()))
+}
 
-    Ok(Response::new()
-        .add_attribute("execute", "withdraw")
-        .add_message(msg))
+fn query_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT) as usize
 }
 
 pub fn query_cw721_deposits(
     deps: Deps,
     owner: String,
     collection: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
 ) -> StdResult<Cw721DepositResponse> {
-    let res: StdResult<Vec<_>> = CW721_DEPOSITS
+    let limit = query_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let deposits = CW721_DEPOSITS
         .prefix((&collection, &owner))
-        .range(deps.storage, None, None, Order::Ascending)
-        .collect();
-    let deposits = res?;
-    Ok(Cw721DepositResponse { deposits })
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = match deposits.last() {
+        Some((token_id, _)) if deposits.len() == limit => Some(token_id.clone()),
+        _ => None,
+    };
+    Ok(Cw721DepositResponse {
+        deposits,
+        next_start_after,
+    })
 }
 
 pub fn query_ask(deps: Deps, collection: String, token_id: String) -> StdResult<AskResponse> {
-    let ask = ASKS.may_load(deps.storage, (&collection, &token_id))?;
+    let ask = asks().may_load(deps.storage, (&collection, &token_id))?;
 
     Ok(AskResponse { ask })
 }
 
-pub fn query_all_asks(deps: Deps) -> StdResult<GetAllAsksResponse> {
-    let res:StdResult<Vec<_>> = ASKS.range(deps.storage, None, None, Order::Ascending).collect();
-    let asks = res?;
-    Ok(GetAllAsksResponse { asks })
+pub fn query_all_asks(
+    deps: Deps,
+    env: Env,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<GetAllAsksResponse> {
+    let limit = query_limit(limit);
+    let start = start_after
+        .as_ref()
+        .map(|(collection, token_id)| Bound::exclusive((collection.as_str(), token_id.as_str())));
+    let asks = asks()
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, ask)) if ask.is_expired(&env.block)))
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = match asks.last() {
+        Some((key, _)) if asks.len() == limit => Some(key.clone()),
+        _ => None,
+    };
+    Ok(GetAllAsksResponse {
+        asks,
+        next_start_after,
+    })
+}
+
+fn paginate_asks(
+    deps: Deps,
+    env: Env,
+    prefix: Prefix<(String, String), Ask, (String, String)>,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<AsksResponse> {
+    let limit = query_limit(limit);
+    let start = start_after.map(Bound::exclusive);
+    let asks = prefix
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, ask)) if ask.is_expired(&env.block)))
+        .take(limit)
+        .map(|item| item.map(|(_, ask)| ask))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = match asks.last() {
+        Some(ask) if asks.len() == limit => {
+            Some((ask.collection.to_string(), ask.token_id.clone()))
+        }
+        _ => None,
+    };
+    Ok(AsksResponse {
+        asks,
+        next_start_after,
+    })
+}
+
+pub fn query_asks_by_seller(
+    deps: Deps,
+    env: Env,
+    seller: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<AsksResponse> {
+    let prefix = asks().idx.seller.prefix(seller);
+    paginate_asks(deps, env, prefix, start_after, limit)
+}
+
+pub fn query_asks_by_collection(
+    deps: Deps,
+    env: Env,
+    collection: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<AsksResponse> {
+    let prefix = asks().idx.collection.prefix(collection);
+    paginate_asks(deps, env, prefix, start_after, limit)
+}
+
+pub fn query_asks_by_payment_token(
+    deps: Deps,
+    env: Env,
+    payment_token: Option<String>,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<AsksResponse> {
+    let payment_token = ask_denom_key(deps, payment_token)?;
+    let prefix = asks().idx.payment_token.prefix(payment_token);
+    paginate_asks(deps, env, prefix, start_after, limit)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn query_asks_by_price(
+    deps: Deps,
+    env: Env,
+    collection: String,
+    payment_token: Option<String>,
+    min: Option<Uint128>,
+    max: Option<Uint128>,
+    order: Option<SortOrder>,
+    start_after: Option<(Uint128, String)>,
+    limit: Option<u32>,
+) -> StdResult<AsksByPriceResponse> {
+    let payment_token = ask_denom_key(deps, payment_token)?;
+    let limit = query_limit(limit);
+    let order = order.unwrap_or(SortOrder::Ascending);
+
+    // an empty primary key sorts before any ask at the same price
+    let lowest_pk = || (String::new(), String::new());
+    let mut lower = min.map(|min| Bound::inclusive((min.u128(), lowest_pk())));
+    let mut upper = max.and_then(|max| {
+        max.u128()
+            .checked_add(1)
+            .map(|bound| Bound::exclusive((bound, lowest_pk())))
+    });
+    if let Some((price, token_id)) = start_after {
+        let cursor = Bound::exclusive((price.u128(), (collection.clone(), token_id)));
+        match order {
+            SortOrder::Ascending if min.map_or(true, |min| price >= min) => lower = Some(cursor),
+            SortOrder::Descending if max.map_or(true, |max| price <= max) => upper = Some(cursor),
+            _ => {}
+        }
+    }
+
+    let asks = asks()
+        .idx
+        .price
+        .sub_prefix((collection, payment_token))
+        .range(deps.storage, lower, upper, order.into())
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, ask)| is_fixed_price_ask(ask, &env.block))
+        })
+        .take(limit)
+        .map(|item| item.map(|(_, ask)| ask))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = match asks.last() {
+        Some(ask) if asks.len() == limit => Some((ask.price, ask.token_id.clone())),
+        _ => None,
+    };
+    Ok(AsksByPriceResponse {
+        asks,
+        next_start_after,
+    })
+}
+
+pub fn query_current_price(
+    deps: Deps,
+    env: Env,
+    collection: String,
+    token_id: String,
+) -> StdResult<CurrentPriceResponse> {
+    let ask = asks()
+        .may_load(deps.storage, (&collection, &token_id))?
+        .filter(|ask| !ask.is_expired(&env.block));
+
+    Ok(CurrentPriceResponse {
+        price: ask.map(|ask| ask.current_price(env.block.time)),
+    })
+}
+
+pub fn query_floor_price(
+    deps: Deps,
+    env: Env,
+    collection: String,
+    payment_token: Option<String>,
+) -> StdResult<FloorPriceResponse> {
+    let payment_token = ask_denom_key(deps, payment_token)?;
+    let ask = asks()
+        .idx
+        .price
+        .sub_prefix((collection, payment_token))
+        .range(deps.storage, None, None, Order::Ascending)
+        .find(|item| {
+            item.as_ref()
+                .map_or(true, |(_, ask)| is_fixed_price_ask(ask, &env.block))
+        })
+        .transpose()?
+        .map(|(_, ask)| ask);
+    Ok(FloorPriceResponse {
+        price: ask.as_ref().map(|ask| ask.current_price(env.block.time)),
+        ask,
+    })
+}
+
+/// The price index holds declining asks at their starting price, so the price sorted
+/// queries only cover live asks with a fixed price
+fn is_fixed_price_ask(ask: &Ask, block: &BlockInfo) -> bool {
+    ask.decline.is_none() && !ask.is_expired(block)
+}
+
+/// Asks and collection bids are indexed by their cw20 address or native denom, the
+/// default native denom if none is given
+fn ask_denom_key(deps: Deps, payment_token: Option<String>) -> StdResult<String> {
+    match payment_token {
+        Some(payment_token) => Ok(payment_token),
+        None => Ok(CONFIG.load(deps.storage)?.native_denom),
+    }
+}
+
+fn paginate_bids(
+    deps: Deps,
+    prefix: Prefix<BidKey, Bid, BidKey>,
+    start_after: Option<BidKey>,
+    limit: Option<u32>,
+) -> StdResult<BidsResponse> {
+    let limit = query_limit(limit);
+    let start = start_after.map(Bound::exclusive);
+    let bids = prefix
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(bids_response(bids, limit))
+}
+
+fn bids_response(bids: Vec<Bid>, limit: usize) -> BidsResponse {
+    let next_start_after = match bids.last() {
+        Some(bid) if bids.len() == limit => Some((
+            bid.collection.to_string(),
+            bid.token_id.clone(),
+            bid.bidder.to_string(),
+        )),
+        _ => None,
+    };
+    BidsResponse {
+        bids,
+        next_start_after,
+    }
+}
+
+pub fn query_bids_for_token(
+    deps: Deps,
+    collection: String,
+    token_id: String,
+    start_after: Option<BidKey>,
+    limit: Option<u32>,
+) -> StdResult<BidsResponse> {
+    let limit = query_limit(limit);
+    let start_after = start_after.map(|(_, _, bidder)| bidder);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let bids = bids()
+        .prefix((&collection, &token_id))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(bids_response(bids, limit))
+}
+
+pub fn query_bids_by_bidder(
+    deps: Deps,
+    bidder: String,
+    start_after: Option<BidKey>,
+    limit: Option<u32>,
+) -> StdResult<BidsResponse> {
+    let prefix = bids().idx.bidder.prefix(bidder);
+    paginate_bids(deps, prefix, start_after, limit)
+}
+
+pub fn query_collection_bid(
+    deps: Deps,
+    collection: String,
+    bidder: String,
+) -> StdResult<CollectionBidResponse> {
+    let bid = collection_bids().may_load(deps.storage, (&collection, &bidder))?;
+
+    Ok(CollectionBidResponse { bid })
+}
+
+pub fn query_top_collection_bids(
+    deps: Deps,
+    collection: String,
+    payment_token: Option<String>,
+    start_after: Option<(Uint128, String)>,
+    limit: Option<u32>,
+) -> StdResult<CollectionBidsResponse> {
+    let limit = query_limit(limit);
+    let end = start_after
+        .map(|(price, bidder)| Bound::exclusive((price.u128(), (collection.clone(), bidder))));
+    let bids = collection_bids()
+        .idx
+        .price
+        .sub_prefix((collection, ask_denom_key(deps, payment_token)?))
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = match bids.last() {
+        Some(bid) if bids.len() == limit => Some((bid.price, bid.bidder.to_string())),
+        _ => None,
+    };
+    Ok(CollectionBidsResponse {
+        bids,
+        next_start_after,
+    })
+}
+
+pub fn query_trait_bid(deps: Deps, id: u64) -> StdResult<TraitBidResponse> {
+    let bid = trait_bids().may_load(deps.storage, id)?;
+
+    Ok(TraitBidResponse { bid })
+}
+
+fn paginate_trait_bids(
+    deps: Deps,
+    prefix: Prefix<u64, TraitBid, u64>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TraitBidsResponse> {
+    let limit = query_limit(limit);
+    let start = start_after.map(Bound::exclusive);
+    let bids = prefix
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = match bids.last() {
+        Some(bid) if bids.len() == limit => Some(bid.id),
+        _ => None,
+    };
+    Ok(TraitBidsResponse {
+        bids,
+        next_start_after,
+    })
+}
+
+pub fn query_trait_bids_for_collection(
+    deps: Deps,
+    collection: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TraitBidsResponse> {
+    let prefix = trait_bids().idx.collection.prefix(collection);
+    paginate_trait_bids(deps, prefix, start_after, limit)
+}
+
+pub fn query_trait_bids_by_bidder(
+    deps: Deps,
+    bidder: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TraitBidsResponse> {
+    let prefix = trait_bids().idx.bidder.prefix(bidder);
+    paginate_trait_bids(deps, prefix, start_after, limit)
+}
+
+pub fn query_auction(
+    deps: Deps,
+    collection: String,
+    token_id: String,
+) -> StdResult<AuctionResponse> {
+    let auction = auctions().may_load(deps.storage, (&collection, &token_id))?;
+
+    Ok(AuctionResponse { auction })
+}
+
+fn auctions_response(auctions: Vec<Auction>, limit: usize) -> AuctionsResponse {
+    let next_start_after = match auctions.last() {
+        Some(auction) if auctions.len() == limit => Some((
+            auction.end_time,
+            auction.collection.to_string(),
+            auction.token_id.clone(),
+        )),
+        _ => None,
+    };
+    AuctionsResponse {
+        auctions,
+        next_start_after,
+    }
+}
+
+pub fn query_active_auctions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<(Timestamp, String, String)>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let limit = query_limit(limit);
+    // an auction is active while its end time is later than the block time
+    let now = env.block.time.nanos();
+    let start = match start_after {
+        Some((end_time, collection, token_id)) if end_time.nanos() > now => {
+            Bound::exclusive((end_time.nanos(), (collection, token_id)))
+        }
+        _ => Bound::inclusive((now + 1, (String::new(), String::new()))),
+    };
+    let auctions = auctions()
+        .idx
+        .end_time
+        .range(deps.storage, Some(start), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, auction)| auction))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(auctions_response(auctions, limit))
+}
+
+pub fn query_ended_auctions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<(Timestamp, String, String)>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let limit = query_limit(limit);
+    let now = env.block.time.nanos();
+    let start = start_after.map(|(end_time, collection, token_id)| {
+        Bound::exclusive((end_time.nanos(), (collection, token_id)))
+    });
+    let end = Bound::exclusive((now + 1, (String::new(), String::new())));
+    let auctions = auctions()
+        .idx
+        .end_time
+        .range(deps.storage, start, Some(end), Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, auction)| auction))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(auctions_response(auctions, limit))
+}
+
+pub fn query_sealed_auction(
+    deps: Deps,
+    collection: String,
+    token_id: String,
+) -> StdResult<SealedAuctionResponse> {
+    let auction = SEALED_AUCTIONS.may_load(deps.storage, (&collection, &token_id))?;
+
+    Ok(SealedAuctionResponse { auction })
+}
+
+pub fn query_sealed_bids(
+    deps: Deps,
+    collection: String,
+    token_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SealedBidsResponse> {
+    let limit = query_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let bids = SEALED_BIDS
+        .prefix((&collection, &token_id))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = match bids.last() {
+        Some(bid) if bids.len() == limit => Some(bid.bidder.to_string()),
+        _ => None,
+    };
+
+    Ok(SealedBidsResponse {
+        bids,
+        next_start_after,
+    })
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(ConfigResponse { config })
+}
+
+pub fn query_pause_status(deps: Deps) -> StdResult<PauseStatusResponse> {
+    let pause_status = PAUSE_STATUS.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(PauseStatusResponse { pause_status })
+}
+
+pub fn query_payment_tokens(deps: Deps) -> StdResult<PaymentTokensResponse> {
+    let tokens = PAYMENT_TOKENS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, token)| token))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PaymentTokensResponse { tokens })
+}
+
+pub fn query_collection(deps: Deps, address: String) -> StdResult<CollectionResponse> {
+    let collection = COLLECTIONS.may_load(deps.storage, &address)?;
+
+    Ok(CollectionResponse { collection })
+}
+
+pub fn query_collections(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CollectionsResponse> {
+    let limit = query_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let collections = COLLECTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, collection)| collection))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = match collections.last() {
+        Some(collection) if collections.len() == limit => Some(collection.address.to_string()),
+        _ => None,
+    };
+
+    Ok(CollectionsResponse {
+        collections,
+        next_start_after,
+    })
+}
+
+pub fn query_royalty(deps: Deps, collection: String) -> StdResult<RoyaltyResponse> {
+    let royalty = ROYALTIES.may_load(deps.storage, &collection)?;
+
+    Ok(RoyaltyResponse { royalty })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::coins;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw_utils::PaymentError;
+
+    const COLLECTION: &str = "collection";
+    const SELLER: &str = "seller";
+
+    fn setup(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            native_denom: "ujunox".to_string(),
+            native_denoms: vec![],
+            admin: None,
+            trading_fee_bps: 0,
+            fee_recipient: "creator".to_string(),
+            max_royalty_bps: 0,
+            collection_mode: CollectionMode::Open,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn create_auction_msg(
+        min_increment_bps: u64,
+        end_time: Timestamp,
+        payment: Denom,
+    ) -> ExecuteMsg {
+        let hook_msg = Cw721HookMsg::CreateAuction {
+            reserve_price: Uint128::new(100),
+            min_increment_bps,
+            start_time: None,
+            end_time,
+            extension_secs: 0,
+            payment,
+        };
+        ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: SELLER.to_string(),
+            token_id: "1".to_string(),
+            msg: to_binary(&hook_msg).unwrap(),
+        })
+    }
+
+    #[test]
+    fn create_auction_validation() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup(deps.as_mut());
+        let native = Denom::Native("ujunox".to_string());
+
+        let msg = create_auction_msg(100, env.block.time, native.clone());
+        let err = execute(deps.as_mut(), env.clone(), mock_info(COLLECTION, &[]), msg);
+        assert!(matches!(err, Err(ContractError::InvalidAuctionTimes {})));
+
+        let end_time = env.block.time.plus_seconds(60);
+        let msg = create_auction_msg(10_001, end_time, native.clone());
+        let err = execute(deps.as_mut(), env.clone(), mock_info(COLLECTION, &[]), msg);
+        assert!(matches!(err, Err(ContractError::InvalidIncrement {})));
+
+        let msg = create_auction_msg(100, end_time, Denom::Native("uatom".to_string()));
+        let err = execute(deps.as_mut(), env.clone(), mock_info(COLLECTION, &[]), msg);
+        assert!(matches!(err, Err(ContractError::DenomNotAllowed { .. })));
+
+        let msg = create_auction_msg(100, end_time, native.clone());
+        execute(deps.as_mut(), env.clone(), mock_info(COLLECTION, &[]), msg).unwrap();
+        let auction = auctions().load(&deps.storage, (COLLECTION, "1")).unwrap();
+        assert_eq!(auction.seller, SELLER.to_string());
+        assert_eq!(auction.highest_bid, None);
+
+        //a second auction for the same token cannot overwrite the running one
+        let msg = create_auction_msg(100, end_time, native);
+        let err = execute(deps.as_mut(), env, mock_info(COLLECTION, &[]), msg);
+        assert!(matches!(err, Err(ContractError::AlreadyListed {})));
+    }
+
+    #[test]
+    fn create_sealed_auction_twice() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup(deps.as_mut());
+
+        let hook_msg = Cw721HookMsg::CreateSealedAuction {
+            reserve_price: Uint128::new(100),
+            commit_end: env.block.time.plus_seconds(60),
+            reveal_end: env.block.time.plus_seconds(120),
+            payment: Denom::Native("ujunox".to_string()),
+        };
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: SELLER.to_string(),
+            token_id: "1".to_string(),
+            msg: to_binary(&hook_msg).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(COLLECTION, &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env, mock_info(COLLECTION, &[]), msg);
+        assert!(matches!(err, Err(ContractError::AlreadyListed {})));
+    }
+
+    #[test]
+    fn listing_and_purchase_errors() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup(deps.as_mut());
+
+        let receive_nft = |hook_msg: Binary| {
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: SELLER.to_string(),
+                token_id: "1".to_string(),
+                msg: hook_msg,
+            })
+        };
+        let msg = receive_nft(Binary::from(br#"{"list":{}}"#.to_vec()));
+        let err = execute(deps.as_mut(), env.clone(), mock_info(COLLECTION, &[]), msg);
+        assert!(matches!(err, Err(ContractError::InvalidHookMsg { .. })));
+
+        let purchase = ExecuteMsg::PurchaseNative {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("buyer", &coins(100, "ujunox")),
+            purchase.clone(),
+        );
+        assert!(matches!(err, Err(ContractError::AskNotFound {})));
+
+        let hook_msg = to_binary(&Cw721HookMsg::SetListing {
+            cw20_contract: None,
+            native_denom: None,
+            amount: 100,
+            expires_at: None,
+            payout_address: None,
+        })
+        .unwrap();
+        let info = mock_info(COLLECTION, &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            receive_nft(hook_msg.clone()),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info, receive_nft(hook_msg));
+        assert!(matches!(err, Err(ContractError::AlreadyListed {})));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("buyer", &[]),
+            purchase.clone(),
+        );
+        assert!(matches!(
+            err,
+            Err(ContractError::Payment(PaymentError::NoFunds {}))
+        ));
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("buyer", &coins(90, "ujunox")),
+            purchase,
+        );
+        assert!(matches!(
+            err,
+            Err(ContractError::WrongPaymentAmount { expected, received })
+                if expected == Uint128::new(100) && received == Uint128::new(90)
+        ));
+    }
+
+    #[test]
+    fn min_auction_bid_increment() {
+        let mut auction = Auction {
+            collection: Addr::unchecked(COLLECTION),
+            token_id: "1".to_string(),
+            seller: Addr::unchecked(SELLER),
+            payment: Denom::Native("ujunox".to_string()),
+            reserve_price: Uint128::new(100),
+            min_increment_bps: 500,
+            start_time: Timestamp::from_seconds(0),
+            end_time: Timestamp::from_seconds(100),
+            extension_secs: 0,
+            highest_bid: None,
+        };
+        assert_eq!(min_auction_bid(&auction), Uint128::new(1));
+
+        auction.highest_bid = Some(AuctionBid {
+            bidder: Addr::unchecked("bidder"),
+            amount: Uint128::new(1000),
+        });
+        assert_eq!(min_auction_bid(&auction), Uint128::new(1050));
+
+        //small bids still have to increase by one
+        auction.highest_bid = Some(AuctionBid {
+            bidder: Addr::unchecked("bidder"),
+            amount: Uint128::new(10),
+        });
+        assert_eq!(min_auction_bid(&auction), Uint128::new(11));
+    }
+
+    #[test]
+    fn declining_price() {
+        let decline = PriceDecline {
+            floor_price: Uint128::new(100),
+            start_time: Timestamp::from_seconds(1000),
+            end_time: Timestamp::from_seconds(1100),
+            step_secs: None,
+        };
+        let start_price = Uint128::new(1100);
+        assert_eq!(
+            decline.price_at(start_price, Timestamp::from_seconds(900)),
+            start_price
+        );
+        assert_eq!(
+            decline.price_at(start_price, Timestamp::from_seconds(1025)),
+            Uint128::new(850)
+        );
+        assert_eq!(
+            decline.price_at(start_price, Timestamp::from_seconds(2000)),
+            Uint128::new(100)
+        );
+
+        let decline = PriceDecline {
+            step_secs: Some(40),
+            ..decline
+        };
+        assert_eq!(
+            decline.price_at(start_price, Timestamp::from_seconds(1039)),
+            start_price
+        );
+        assert_eq!(
+            decline.price_at(start_price, Timestamp::from_seconds(1085)),
+            Uint128::new(300)
+        );
+    }
+
+    #[test]
+    fn declining_price_floor_below_start() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup(deps.as_mut());
+
+        let dutch_listing = |floor_price: u128| {
+            let hook_msg = Cw721HookMsg::SetDutchListing {
+                cw20_contract: None,
+                native_denom: None,
+                start_price: Uint128::new(100),
+                floor_price: Uint128::new(floor_price),
+                start_time: None,
+                end_time: env.block.time.plus_seconds(60),
+                step_secs: None,
+                expires_at: None,
+                payout_address: None,
+            };
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: SELLER.to_string(),
+                token_id: "1".to_string(),
+                msg: to_binary(&hook_msg).unwrap(),
+            })
+        };
+        //a floor equal to the starting price does not decline
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(COLLECTION, &[]),
+            dutch_listing(100),
+        );
+        assert!(matches!(err, Err(ContractError::InvalidPriceDecline {})));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(COLLECTION, &[]),
+            dutch_listing(50),
+        )
+        .unwrap();
+
+        //nor can the starting price be lowered down to the floor
+        let update_ask = ExecuteMsg::UpdateAsk {
+            collection: COLLECTION.to_string(),
+            token_id: "1".to_string(),
+            price: Some(Uint128::new(50)),
+            payment: None,
+            expires: None,
+        };
+        let err = execute(deps.as_mut(), env, mock_info(SELLER, &[]), update_ask);
+        assert!(matches!(err, Err(ContractError::InvalidPriceDecline {})));
+    }
+
+    #[test]
+    fn migrate_requires_current_storage_layout() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup(deps.as_mut());
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+        //a deployment from before the layout change has to be redeployed
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), env, MigrateMsg {}).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Std(StdError::GenericErr { .. })
+        ));
+    }
 }
@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,8 +7,35 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("Invalid address: {address}")]
+    InvalidAddress { address: String },
+
     #[error("This token is not listed for sale")]
-    TokenNotListedForSale {},
+    AskNotFound {},
+
+    #[error("This token is already listed by its owner")]
+    AlreadyListed {},
+
+    #[error("Marketplace is not approved to transfer this token")]
+    NotApproved {},
+
+    #[error("Seller no longer owns this token or has revoked the marketplace approval")]
+    StaleAsk {},
+
+    #[error("Seller still owns this token and has it approved for the marketplace")]
+    AskNotStale {},
+
+    #[error("Payment of {received} does not match the price of {expected}")]
+    WrongPaymentAmount {
+        expected: Uint128,
+        received: Uint128,
+    },
+
+    #[error("Invalid hook message: {reason}")]
+    InvalidHookMsg { reason: String },
 
     #[error("Contract does not possess token_id from this cw721 to withdraw")]
     NoCw721ToWithdraw {},
@@ -15,6 +43,99 @@ pub enum ContractError {
     #[error("Unauthorized - Only owner can execute this operation")]
     UnauthorizedOwner {},
 
-    #[error("Custom Error val: {val:?}")]
-    CustomError { val: String },
+    #[error("Unauthorized - Only the admin can execute this operation")]
+    Unauthorized {},
+
+    #[error("Marketplace is paused for {operation}")]
+    Paused { operation: String },
+
+    #[error("Trading fee and max royalty together cannot exceed 10000 basis points")]
+    InvalidTradingFee {},
+
+    #[error("Royalty cannot exceed {max_bps} basis points")]
+    InvalidRoyalty { max_bps: u64 },
+
+    #[error("Unauthorized - Only the collection minter can execute this operation")]
+    NotCollectionMinter {},
+
+    #[error("Collection is not allowed to trade on this marketplace")]
+    CollectionNotAllowed {},
+
+    #[error("No bid from this bidder on this token")]
+    BidNotFound {},
+
+    #[error("This ask has expired")]
+    AskExpired {},
+
+    #[error("This bid has expired")]
+    BidExpired {},
+
+    #[error("Token metadata does not meet the bid criteria")]
+    CriteriaNotMet {},
+
+    #[error("Expiration is already in the past")]
+    InvalidExpiration {},
+
+    #[error("Sale is priced in {expected}, not {received}")]
+    WrongPaymentToken { expected: String, received: String },
+
+    #[error("Sale is priced in cw20 {cw20_contract}, pay it through the cw20 contract")]
+    NativePaymentForCw20Ask { cw20_contract: String },
+
+    #[error("Native denom {denom} is not accepted for payment on this marketplace")]
+    DenomNotAllowed { denom: String },
+
+    #[error("Listing can be priced in a cw20 or a native denom, not both")]
+    AmbiguousPayment {},
+
+    #[error("Cw20 is not accepted for payment on this marketplace")]
+    PaymentTokenNotAllowed {},
+
+    #[error("No auction for this token")]
+    AuctionNotFound {},
+
+    #[error("Auction must end after it starts and in the future")]
+    InvalidAuctionTimes {},
+
+    #[error("Declining price must end after it starts, above zero steps and at a floor below the starting price")]
+    InvalidPriceDecline {},
+
+    #[error("Minimum increment cannot exceed 10000 basis points")]
+    InvalidIncrement {},
+
+    #[error("Auction has not started yet")]
+    AuctionNotStarted {},
+
+    #[error("Auction has ended")]
+    AuctionEnded {},
+
+    #[error("Auction has not ended yet")]
+    AuctionNotEnded {},
+
+    #[error("Bid must be at least {min}")]
+    BidTooLow { min: Uint128 },
+
+    #[error("Seller cannot bid on their own auction")]
+    SellerCannotBid {},
+
+    #[error("Commit phase has ended")]
+    CommitPhaseEnded {},
+
+    #[error("Auction is not in its reveal phase")]
+    NotRevealPhase {},
+
+    #[error("Bidder already committed a bid to this auction")]
+    AlreadyCommitted {},
+
+    #[error("Auction already holds the maximum of {max} sealed bids")]
+    TooManySealedBids { max: u32 },
+
+    #[error("Bid was already revealed")]
+    AlreadyRevealed {},
+
+    #[error("Revealed amount and salt do not match the commitment")]
+    InvalidReveal {},
+
+    #[error("Revealed amount exceeds the collateral of {collateral}")]
+    CollateralTooLow { collateral: Uint128 },
 }
@@ -1,26 +1,41 @@
 #[cfg(test)]
 mod tests {
+    use crate::contract::sealed_bid_commitment;
     use crate::helpers::MarketplaceContract;
     use crate::msg::{
-        AskResponse, Cw20HookMsg, Cw721DepositResponse, Cw721HookMsg, ExecuteMsg, InstantiateMsg,
-        QueryMsg,
+        AskResponse, AsksByPriceResponse, AsksResponse, AuctionResponse, AuctionsResponse,
+        BidsResponse, CollectionBidsResponse, CollectionResponse, CollectionsResponse,
+        ConfigResponse, CurrentPriceResponse, Cw20HookMsg, Cw721DepositResponse, Cw721HookMsg,
+        ExecuteMsg, FloorPriceResponse, GetAllAsksResponse, InstantiateMsg, PauseStatusResponse,
+        PaymentTokensResponse, QueryMsg, RoyaltyResponse, SealedAuctionResponse,
+        SealedBidsResponse, SortOrder, TraitBidsResponse,
     };
-    use cosmwasm_std::{coin, coins, to_binary, Addr, Coin, Empty, Uint128};
-    use cw20::{BalanceResponse, Cw20Coin, Cw20Contract, MinterResponse};
+    use crate::state::{CollectionMode, MetadataCriteria};
+    use crate::ContractError;
+    use cosmwasm_std::{
+        coin, coins, to_binary, Addr, Binary, Coin, DepsMut, Empty, Env, MessageInfo, Response,
+        Uint128,
+    };
+    use cw20::{BalanceResponse, Cw20Coin, Cw20Contract, Denom};
     use cw20_base::msg::ExecuteMsg as Cw20ExecuteMsg;
     use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
     use cw20_base::msg::QueryMsg as Cw20QueryMsg;
     use cw721::OwnerOfResponse;
-    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+    use cw_multi_test::{App, AppBuilder, AppResponse, Contract, ContractWrapper, Executor};
+    use cw_utils::Expiration;
 
     use cw20_impl::{self};
+    use nft::contract::Metadata;
     use nft::helpers::NftContract;
     use nft::{self};
 
     const USER: &str = "juno10c3slrqx3369mfsr9670au22zvq082jaej8ve4";
     const BUYER: &str = "juno10c3slrqx3369mfsr9670au22zvq082jaejxx23";
+    const BIDDER: &str = "juno1x7s8r5xk7z0dfqvjw3e8l4c2ytcx5a0ncmktsu";
     const ADMIN: &str = "ADMIN";
+    const TREASURY: &str = "juno1treasury0000000000000000000000000000000";
     const NATIVE_DENOM: &str = "ujunox";
+    const USDC_DENOM: &str = "uusdc";
     const TOKEN_ID: &str = "0";
 
     pub fn contract_deposit_cw20() -> Box<dyn Contract<Empty>> {
@@ -41,9 +56,20 @@ mod tests {
         Box::new(contract)
     }
 
+    // The nft entry point only exposes minting and metadata updates, so the collection
+    // under test runs the full cw721-base handler over the same metadata extension.
+    fn nft_execute(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: nft::contract::Cw721ExecuteMsg,
+    ) -> Result<Response, cw721_base::ContractError> {
+        nft::contract::Cw721MetadaNonTransferableContract::default().execute(deps, env, info, msg)
+    }
+
     pub fn contract_nft() -> Box<dyn Contract<Empty>> {
         let contract = ContractWrapper::new(
-            nft::contract::entry::execute,
+            nft_execute,
             nft::contract::entry::instantiate,
             nft::contract::entry::query,
         );
@@ -74,6 +100,17 @@ mod tests {
                     }],
                 )
                 .unwrap();
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(BIDDER),
+                    vec![Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(10000),
+                    }],
+                )
+                .unwrap();
         })
     }
 
@@ -86,8 +123,22 @@ mod tests {
     }
 
     fn marketplace_instantiate(app: &mut App, marketplace_id: u64) -> MarketplaceContract {
+        marketplace_instantiate_with_fee(app, marketplace_id, 0)
+    }
+
+    fn marketplace_instantiate_with_fee(
+        app: &mut App,
+        marketplace_id: u64,
+        trading_fee_bps: u64,
+    ) -> MarketplaceContract {
         let msg = InstantiateMsg {
-            native_denom: "ujunox".to_string()
+            native_denom: "ujunox".to_string(),
+            native_denoms: vec![],
+            admin: None,
+            trading_fee_bps,
+            fee_recipient: TREASURY.to_string(),
+            max_royalty_bps: 1000,
+            collection_mode: CollectionMode::Open,
         };
         let marketplace_contract_address = app
             .instantiate_contract(
@@ -112,6 +163,10 @@ mod tests {
                 address: BUYER.to_string(),
                 amount: Uint128::from(10000u64),
             },
+            Cw20Coin {
+                address: BIDDER.to_string(),
+                amount: Uint128::from(10000u64),
+            },
         ];
         let msg: Cw20InstantiateMsg = Cw20InstantiateMsg {
             decimals: 10,
@@ -134,6 +189,22 @@ mod tests {
         Cw20Contract(cw20_contract_address)
     }
 
+    fn allow_payment_token(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw20_contract: &Cw20Contract,
+    ) {
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &ExecuteMsg::AddPaymentToken {
+                address: cw20_contract.addr().to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
     pub fn cw721_instantiate(
         app: &mut App,
         nft_id: u64,
@@ -178,6 +249,8 @@ mod tests {
                 &QueryMsg::Cw721Deposits {
                     owner: USER.to_string(),
                     collection: nft_contract.addr().to_string(),
+                    start_after: None,
+                    limit: None,
                 },
             )
             .unwrap()
@@ -200,6 +273,20 @@ mod tests {
             .unwrap()
     }
 
+    fn get_all_asks(
+        app: &App,
+        marketplace_contract: &MarketplaceContract,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> GetAllAsksResponse {
+        app.wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::GetAllAsks { start_after, limit },
+            )
+            .unwrap()
+    }
+
     fn get_owner_of(app: &App, nft_contract: &NftContract, token_id: String) -> OwnerOfResponse {
         app.wrap()
             .query_wasm_smart(
@@ -218,7 +305,7 @@ mod tests {
         token_id: String,
         token_uri: Option<String>,
         to: String,
-    ) -> () {
+    ) {
         let mint_msg = cw721_base::MintMsg {
             token_id,
             owner: to,
@@ -238,15 +325,33 @@ mod tests {
         token_id: String,
         amount: u128,
     ) {
-        let cw20_contract_address = match cw20_contract {
-            Some(cw20_token) => Some(cw20_token.addr().to_string()),
-            None => None,
-        };
+        list_nft_from(
+            app,
+            USER,
+            marketplace_contract,
+            cw721_contract,
+            cw20_contract,
+            token_id,
+            amount,
+        )
+    }
+
+    fn list_nft_from(
+        app: &mut App,
+        seller: &str,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        cw20_contract: Option<&Cw20Contract>,
+        token_id: String,
+        amount: u128,
+    ) {
+        let cw20_contract_address = cw20_contract.map(|cw20_token| cw20_token.addr().to_string());
         let hook_msg = Cw721HookMsg::SetListing {
-            owner: USER.to_string(),
-            token_id: token_id.clone(),
             cw20_contract: cw20_contract_address,
+            native_denom: None,
             amount,
+            expires_at: None,
+            payout_address: None,
         };
         let msg = nft::contract::Cw721ExecuteMsg::SendNft {
             contract: marketplace_contract.addr().to_string(),
@@ -254,7 +359,7 @@ mod tests {
             msg: to_binary(&hook_msg).unwrap(),
         };
         let cosmos_msg = cw721_contract.call(msg).unwrap();
-        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        app.execute(Addr::unchecked(seller), cosmos_msg).unwrap();
     }
 
     fn buy_nft(
@@ -295,6 +400,240 @@ mod tests {
         };
     }
 
+    fn set_bid(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        bidder: &str,
+        token_id: String,
+        amount: u128,
+    ) {
+        let msg = ExecuteMsg::SetBid {
+            collection: cw721_contract.addr().to_string(),
+            token_id,
+            expires: None,
+        };
+        app.execute_contract(
+            Addr::unchecked(bidder),
+            marketplace_contract.addr(),
+            &msg,
+            &coins(amount, NATIVE_DENOM),
+        )
+        .unwrap();
+    }
+
+    fn set_bid_cw20(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        cw20_contract: &Cw20Contract,
+        bidder: &str,
+        token_id: String,
+        amount: u128,
+    ) {
+        let hook_msg = Cw20HookMsg::SetBid {
+            collection: cw721_contract.addr().to_string(),
+            token_id,
+            expires: None,
+        };
+        let msg = Cw20ExecuteMsg::Send {
+            contract: marketplace_contract.addr().to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        let cosmos_msg = cw20_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(bidder), cosmos_msg).unwrap();
+    }
+
+    fn get_bids_for_token(
+        app: &App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        token_id: String,
+    ) -> BidsResponse {
+        app.wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::BidsForToken {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    }
+
+    fn send_nft_hook(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        token_id: String,
+        hook_msg: Cw721HookMsg,
+    ) {
+        let msg = nft::contract::Cw721ExecuteMsg::SendNft {
+            contract: marketplace_contract.addr().to_string(),
+            token_id,
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        let cosmos_msg = cw721_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+    }
+
+    fn place_auction_bid(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        bidder: &str,
+        token_id: String,
+        amount: u128,
+    ) -> Result<AppResponse, ContractError> {
+        let msg = ExecuteMsg::PlaceAuctionBid {
+            collection: cw721_contract.addr().to_string(),
+            token_id,
+        };
+        app.execute_contract(
+            Addr::unchecked(bidder),
+            marketplace_contract.addr(),
+            &msg,
+            &coins(amount, NATIVE_DENOM),
+        )
+        .map_err(|err| err.downcast().unwrap())
+    }
+
+    fn settle_auction(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        token_id: String,
+    ) -> Result<AppResponse, ContractError> {
+        let msg = ExecuteMsg::SettleAuction {
+            collection: cw721_contract.addr().to_string(),
+            token_id,
+        };
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &msg,
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+    }
+
+    fn get_auction(
+        app: &App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        token_id: String,
+    ) -> AuctionResponse {
+        app.wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::Auction {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id,
+                },
+            )
+            .unwrap()
+    }
+
+    fn get_auctions(
+        app: &App,
+        marketplace_contract: &MarketplaceContract,
+        query: QueryMsg,
+    ) -> AuctionsResponse {
+        app.wrap()
+            .query_wasm_smart(marketplace_contract.addr(), &query)
+            .unwrap()
+    }
+
+    fn get_current_price(
+        app: &App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        token_id: String,
+    ) -> CurrentPriceResponse {
+        app.wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::CurrentPrice {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id,
+                },
+            )
+            .unwrap()
+    }
+
+    fn commit_sealed_bid(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        bidder: &str,
+        amount: u128,
+        salt: &str,
+        collateral: u128,
+    ) -> Result<AppResponse, ContractError> {
+        let msg = ExecuteMsg::CommitSealedBid {
+            collection: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            commitment: sealed_bid_commitment(
+                cw721_contract.addr().as_str(),
+                TOKEN_ID,
+                bidder,
+                Uint128::new(amount),
+                salt,
+            ),
+        };
+        app.execute_contract(
+            Addr::unchecked(bidder),
+            marketplace_contract.addr(),
+            &msg,
+            &coins(collateral, NATIVE_DENOM),
+        )
+        .map_err(|err| err.downcast().unwrap())
+    }
+
+    fn reveal_sealed_bid(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+        bidder: &str,
+        amount: u128,
+        salt: &str,
+    ) -> Result<AppResponse, ContractError> {
+        let msg = ExecuteMsg::RevealSealedBid {
+            collection: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            amount: Uint128::new(amount),
+            salt: salt.to_string(),
+        };
+        app.execute_contract(
+            Addr::unchecked(bidder),
+            marketplace_contract.addr(),
+            &msg,
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+    }
+
+    fn settle_sealed_auction(
+        app: &mut App,
+        marketplace_contract: &MarketplaceContract,
+        cw721_contract: &NftContract,
+    ) -> Result<AppResponse, ContractError> {
+        let msg = ExecuteMsg::SettleSealedAuction {
+            collection: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+        };
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &msg,
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+    }
+
     #[test]
     fn mint_then_list_nft_cw20_ask() {
         let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
@@ -307,6 +646,7 @@ mod tests {
             USER.to_string(),
         );
         let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
 
         //mint a new NFT with token_id "0"
         mint_nft(
@@ -356,7 +696,7 @@ mod tests {
 
     #[test]
     fn mint_then_list_nft_native_ask() {
-        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let (mut app, marketplace_id, _cw20_id, cw721_id) = store_code();
         let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
         let cw721_contract = cw721_instantiate(
             &mut app,
@@ -414,7 +754,7 @@ mod tests {
 
     #[test]
     fn purchase_nft_native() {
-        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let (mut app, marketplace_id, _cw20_id, cw721_id) = store_code();
         let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
         let cw721_contract = cw721_instantiate(
             &mut app,
@@ -473,6 +813,7 @@ mod tests {
             USER.to_string(),
         );
         let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
 
         //mint a new NFT with token_id "0"
         mint_nft(
@@ -505,9 +846,3675 @@ mod tests {
 
         let owner_res = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
         assert_eq!(owner_res.owner, BUYER.to_string());
-        let post_buy_seller_balance = get_balance(&mut app, &cw20_contract, USER.to_string());
+        let post_buy_seller_balance = get_balance(&app, &cw20_contract, USER.to_string());
         assert_eq!(post_buy_seller_balance.balance, Uint128::from(10500u128));
-        let post_buy_buyer_balance = get_balance(&mut app, &cw20_contract, BUYER.to_string());
+        let post_buy_buyer_balance = get_balance(&app, &cw20_contract, BUYER.to_string());
         assert_eq!(post_buy_buyer_balance.balance, Uint128::from(9500u128));
     }
+
+    #[test]
+    fn paginate_asks_and_deposits() {
+        let (mut app, marketplace_id, _cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let collection = cw721_contract.addr().to_string();
+
+        //mint and list more NFTs than the maximum page size
+        for i in 0..35 {
+            let token_id = format!("{:02}", i);
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.clone(),
+                None,
+                USER.to_string(),
+            );
+            list_nft(
+                &mut app,
+                &marketplace_contract,
+                &cw721_contract,
+                None,
+                token_id,
+                500,
+            );
+        }
+
+        //first page is full and returns a cursor
+        let page = get_all_asks(&app, &marketplace_contract, None, Some(2));
+        assert_eq!(page.asks.len(), 2);
+        assert_eq!(
+            page.next_start_after,
+            Some((collection.clone(), "01".to_string()))
+        );
+
+        //without a limit the default page size applies
+        let page = get_all_asks(&app, &marketplace_contract, None, None);
+        assert_eq!(page.asks.len(), 10);
+
+        //limit is capped at the hard maximum
+        let page = get_all_asks(&app, &marketplace_contract, None, Some(1000));
+        assert_eq!(page.asks.len(), 30);
+        assert_eq!(
+            page.next_start_after,
+            Some((collection.clone(), "29".to_string()))
+        );
+
+        //last page is partial and has no cursor
+        let page = get_all_asks(
+            &app,
+            &marketplace_contract,
+            page.next_start_after,
+            Some(1000),
+        );
+        assert_eq!(page.asks.len(), 5);
+        assert_eq!(page.asks[0].1.token_id, "30".to_string());
+        assert_eq!(page.next_start_after, None);
+
+        //deposits are paginated by token_id
+        let deposits: Cw721DepositResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::Cw721Deposits {
+                    owner: USER.to_string(),
+                    collection: collection.clone(),
+                    start_after: Some("00".to_string()),
+                    limit: Some(1),
+                },
+            )
+            .unwrap();
+        assert_eq!(deposits.deposits.len(), 1);
+        assert_eq!(deposits.deposits[0].0, "01".to_string());
+        assert_eq!(deposits.next_start_after, Some("01".to_string()));
+    }
+
+    #[test]
+    fn query_asks_by_index() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let other_cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "OTHER".to_string(),
+            "OTHER".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+
+        //USER lists "0" for cw20 and "1" for native, BUYER lists "2" for native
+        for (token_id, owner) in [("0", USER), ("1", USER), ("2", BUYER)] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                owner.to_string(),
+            );
+        }
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            Some(&cw20_contract),
+            "0".to_string(),
+            500,
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "1".to_string(),
+            500,
+        );
+        list_nft_from(
+            &mut app,
+            BUYER,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "2".to_string(),
+            500,
+        );
+        //USER lists "0" of another collection for native
+        mint_nft(
+            &mut app,
+            &other_cw721_contract,
+            "0".to_string(),
+            None,
+            USER.to_string(),
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &other_cw721_contract,
+            None,
+            "0".to_string(),
+            500,
+        );
+
+        let query = |app: &App, msg: QueryMsg| -> AsksResponse {
+            app.wrap()
+                .query_wasm_smart(marketplace_contract.addr(), &msg)
+                .unwrap()
+        };
+
+        let by_seller = query(
+            &app,
+            QueryMsg::AsksBySeller {
+                seller: USER.to_string(),
+                start_after: None,
+                limit: Some(2),
+            },
+        );
+        assert_eq!(by_seller.asks.len(), 2);
+        assert!(by_seller.asks.iter().all(|ask| ask.seller == USER));
+        let by_seller = query(
+            &app,
+            QueryMsg::AsksBySeller {
+                seller: USER.to_string(),
+                start_after: by_seller.next_start_after,
+                limit: Some(2),
+            },
+        );
+        assert_eq!(by_seller.asks.len(), 1);
+
+        let by_collection = query(
+            &app,
+            QueryMsg::AsksByCollection {
+                collection: cw721_contract.addr().to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        let token_ids: Vec<_> = by_collection
+            .asks
+            .iter()
+            .map(|ask| ask.token_id.as_str())
+            .collect();
+        assert_eq!(token_ids, vec!["0", "1", "2"]);
+
+        let by_cw20 = query(
+            &app,
+            QueryMsg::AsksByPaymentToken {
+                payment_token: Some(cw20_contract.addr().to_string()),
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert_eq!(by_cw20.asks.len(), 1);
+        assert_eq!(by_cw20.asks[0].token_id, "0".to_string());
+        let by_native = query(
+            &app,
+            QueryMsg::AsksByPaymentToken {
+                payment_token: None,
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert_eq!(by_native.asks.len(), 3);
+
+        //a sold ask is dropped from the indexes
+        buy_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "2".to_string(),
+            500,
+        );
+        let by_seller = query(
+            &app,
+            QueryMsg::AsksBySeller {
+                seller: BUYER.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert!(by_seller.asks.is_empty());
+    }
+
+    #[test]
+    fn query_asks_by_price_and_floor() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        let collection = cw721_contract.addr().to_string();
+
+        //three native asks and one cheaper cw20 ask
+        for (token_id, price) in [("0", 300), ("1", 100), ("2", 200), ("3", 50)] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+            let cw20 = if token_id == "3" {
+                Some(&cw20_contract)
+            } else {
+                None
+            };
+            list_nft(
+                &mut app,
+                &marketplace_contract,
+                &cw721_contract,
+                cw20,
+                token_id.to_string(),
+                price,
+            );
+        }
+
+        let by_price = |app: &App,
+                        min: Option<u128>,
+                        max: Option<u128>,
+                        order: SortOrder,
+                        start_after: Option<(Uint128, String)>,
+                        limit: Option<u32>|
+         -> AsksByPriceResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    marketplace_contract.addr(),
+                    &QueryMsg::AsksByPrice {
+                        collection: collection.clone(),
+                        payment_token: None,
+                        min: min.map(Uint128::new),
+                        max: max.map(Uint128::new),
+                        order: Some(order),
+                        start_after,
+                        limit,
+                    },
+                )
+                .unwrap()
+        };
+        let prices = |res: &AsksByPriceResponse| -> Vec<u128> {
+            res.asks.iter().map(|ask| ask.price.u128()).collect()
+        };
+
+        let res = by_price(&app, None, None, SortOrder::Ascending, None, None);
+        assert_eq!(prices(&res), vec![100, 200, 300]);
+
+        let res = by_price(&app, None, None, SortOrder::Descending, None, Some(2));
+        assert_eq!(prices(&res), vec![300, 200]);
+        assert_eq!(
+            res.next_start_after,
+            Some((Uint128::new(200), "2".to_string()))
+        );
+        let res = by_price(
+            &app,
+            None,
+            None,
+            SortOrder::Descending,
+            res.next_start_after,
+            Some(2),
+        );
+        assert_eq!(prices(&res), vec![100]);
+        assert_eq!(res.next_start_after, None);
+
+        let res = by_price(&app, Some(150), Some(300), SortOrder::Ascending, None, None);
+        assert_eq!(prices(&res), vec![200, 300]);
+
+        let floor: FloorPriceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::FloorPrice {
+                    collection: collection.clone(),
+                    payment_token: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(floor.price, Some(Uint128::new(100)));
+        assert_eq!(floor.ask.unwrap().token_id, "1".to_string());
+
+        let floor: FloorPriceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::FloorPrice {
+                    collection,
+                    payment_token: Some(cw20_contract.addr().to_string()),
+                },
+            )
+            .unwrap();
+        assert_eq!(floor.price, Some(Uint128::new(50)));
+    }
+
+    #[test]
+    fn accept_bid_on_unlisted_nft() {
+        let (mut app, marketplace_id, _cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+
+        //the bid escrows the coins sent
+        set_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            300,
+        );
+        let bids = get_bids_for_token(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(bids.bids.len(), 1);
+        assert_eq!(bids.bids[0].price, Uint128::new(300));
+        let by_bidder: BidsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::BidsByBidder {
+                    bidder: BUYER.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(by_bidder.bids, bids.bids);
+        assert_eq!(
+            app.wrap().query_all_balances(BUYER).unwrap(),
+            vec![coin(9700, NATIVE_DENOM)]
+        );
+
+        let accept_msg = ExecuteMsg::AcceptBid {
+            collection: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            bidder: BUYER.to_string(),
+        };
+
+        //only the owner may accept
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &accept_msg,
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::UnauthorizedOwner {}
+        ));
+
+        //the owner approves the marketplace and accepts
+        let approve_msg = nft::contract::Cw721ExecuteMsg::Approve {
+            spender: marketplace_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            expires: None,
+        };
+        app.execute(
+            Addr::unchecked(USER),
+            cw721_contract.call(approve_msg).unwrap(),
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &accept_msg,
+            &[],
+        )
+        .unwrap();
+
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10300, NATIVE_DENOM)]
+        );
+        let bids = get_bids_for_token(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert!(bids.bids.is_empty());
+    }
+
+    #[test]
+    fn replace_remove_and_accept_bid_on_listed_nft() {
+        let (mut app, marketplace_id, _cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            TOKEN_ID.to_string(),
+            500,
+        );
+
+        //a second bid replaces the first and refunds it
+        for amount in [200, 250] {
+            set_bid(
+                &mut app,
+                &marketplace_contract,
+                &cw721_contract,
+                BUYER,
+                TOKEN_ID.to_string(),
+                amount,
+            );
+        }
+        assert_eq!(
+            app.wrap().query_all_balances(BUYER).unwrap(),
+            vec![coin(9750, NATIVE_DENOM)]
+        );
+
+        //removing the bid refunds the escrow
+        let remove_msg = ExecuteMsg::RemoveBid {
+            collection: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+        };
+        app.execute_contract(
+            Addr::unchecked(BUYER),
+            marketplace_contract.addr(),
+            &remove_msg,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_all_balances(BUYER).unwrap(),
+            vec![coin(10000, NATIVE_DENOM)]
+        );
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &remove_msg,
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::BidNotFound {}
+        ));
+
+        //the seller accepts a new bid straight from escrow
+        set_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            400,
+        );
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::AcceptBid {
+                collection: cw721_contract.addr().to_string(),
+                token_id: TOKEN_ID.to_string(),
+                bidder: BUYER.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10400, NATIVE_DENOM)]
+        );
+        let ask = get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(ask.ask, None);
+    }
+
+    #[test]
+    fn cw20_bid_refund_and_accept() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            TOKEN_ID.to_string(),
+            500,
+        );
+
+        //the bid escrows the cw20 sent
+        set_bid_cw20(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            &cw20_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            300,
+        );
+        let bids = get_bids_for_token(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(bids.bids[0].payment, Denom::Cw20(cw20_contract.addr()));
+        let buyer_balance = get_balance(&app, &cw20_contract, BUYER.to_string());
+        assert_eq!(buyer_balance.balance, Uint128::new(9700));
+
+        //replacing it with a native bid refunds the cw20
+        set_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            100,
+        );
+        let buyer_balance = get_balance(&app, &cw20_contract, BUYER.to_string());
+        assert_eq!(buyer_balance.balance, Uint128::new(10000));
+
+        //removing the native bid and bidding in cw20 again
+        app.execute_contract(
+            Addr::unchecked(BUYER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::RemoveBid {
+                collection: cw721_contract.addr().to_string(),
+                token_id: TOKEN_ID.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        set_bid_cw20(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            &cw20_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            400,
+        );
+
+        //accepting pays the seller in the cw20
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::AcceptBid {
+                collection: cw721_contract.addr().to_string(),
+                token_id: TOKEN_ID.to_string(),
+                bidder: BUYER.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        let seller_balance = get_balance(&app, &cw20_contract, USER.to_string());
+        assert_eq!(seller_balance.balance, Uint128::new(10400));
+        assert_eq!(
+            app.wrap().query_all_balances(BUYER).unwrap(),
+            vec![coin(10000, NATIVE_DENOM)]
+        );
+    }
+
+    #[test]
+    fn fill_top_collection_bid() {
+        let (mut app, marketplace_id, _cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+
+        for (bidder, amount) in [(USER, 200), (BUYER, 300)] {
+            app.execute_contract(
+                Addr::unchecked(bidder),
+                marketplace_contract.addr(),
+                &ExecuteMsg::SetCollectionBid {
+                    collection: cw721_contract.addr().to_string(),
+                    expires: None,
+                },
+                &coins(amount, NATIVE_DENOM),
+            )
+            .unwrap();
+        }
+
+        let top_bids_in = |app: &App, payment_token: Option<String>| -> CollectionBidsResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    marketplace_contract.addr(),
+                    &QueryMsg::TopCollectionBids {
+                        collection: cw721_contract.addr().to_string(),
+                        payment_token,
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap()
+        };
+        let top_bids = |app: &App| top_bids_in(app, None);
+        let bids = top_bids(&app);
+        let bidders: Vec<_> = bids.bids.iter().map(|bid| bid.bidder.as_str()).collect();
+        assert_eq!(bidders, vec![BUYER, USER]);
+        //the native denom can also be named explicitly
+        let bids = top_bids_in(&app, Some(NATIVE_DENOM.to_string()));
+        assert_eq!(bids.bids.len(), 2);
+        assert_eq!(
+            bids.bids[0].payment,
+            Denom::Native(NATIVE_DENOM.to_string())
+        );
+
+        //the holder fills the top bid by sending the NFT
+        let hook_msg = Cw721HookMsg::AcceptCollectionBid {
+            bidder: BUYER.to_string(),
+        };
+        let msg = nft::contract::Cw721ExecuteMsg::SendNft {
+            contract: marketplace_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        app.execute(Addr::unchecked(USER), cw721_contract.call(msg).unwrap())
+            .unwrap();
+
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10100, NATIVE_DENOM)]
+        );
+        let bids = top_bids(&app);
+        assert_eq!(bids.bids.len(), 1);
+
+        //the remaining bid is refunded on removal
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::RemoveCollectionBid {
+                collection: cw721_contract.addr().to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10300, NATIVE_DENOM)]
+        );
+        assert!(top_bids(&app).bids.is_empty());
+    }
+
+    #[test]
+    fn fill_trait_bid_only_with_matching_metadata() {
+        let (mut app, marketplace_id, _cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+
+        //"0" has the skill the bid asks for, "1" does not
+        for (token_id, skills) in [("0", vec!["rust"]), ("1", vec!["go"])] {
+            let mint_msg = cw721_base::MintMsg {
+                token_id: token_id.to_string(),
+                owner: USER.to_string(),
+                token_uri: None,
+                extension: Some(nft::contract::Metadata {
+                    skills: Some(skills.into_iter().map(String::from).collect()),
+                    ..nft::contract::Metadata::default()
+                }),
+            };
+            let msg = nft::contract::Cw721ExecuteMsg::Mint(mint_msg);
+            app.execute(Addr::unchecked(USER), cw721_contract.call(msg).unwrap())
+                .unwrap();
+        }
+
+        app.execute_contract(
+            Addr::unchecked(BUYER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::SetTraitBid {
+                collection: cw721_contract.addr().to_string(),
+                criteria: MetadataCriteria::Skill {
+                    skill: "rust".to_string(),
+                },
+                expires: None,
+            },
+            &coins(300, NATIVE_DENOM),
+        )
+        .unwrap();
+        let bids: TraitBidsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::TraitBidsForCollection {
+                    collection: cw721_contract.addr().to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(bids.bids.len(), 1);
+        let id = bids.bids[0].id;
+
+        let accept_msg = |token_id: &str| nft::contract::Cw721ExecuteMsg::SendNft {
+            contract: marketplace_contract.addr().to_string(),
+            token_id: token_id.to_string(),
+            msg: to_binary(&Cw721HookMsg::AcceptTraitBid { id }).unwrap(),
+        };
+
+        //a token without the skill is rejected
+        let err = app
+            .execute(
+                Addr::unchecked(USER),
+                cw721_contract.call(accept_msg("1")).unwrap(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::CriteriaNotMet {})
+        ));
+
+        //a token with the skill fills the bid
+        app.execute(
+            Addr::unchecked(USER),
+            cw721_contract.call(accept_msg("0")).unwrap(),
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        let owner = get_owner_of(&app, &cw721_contract, "1".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10300, NATIVE_DENOM)]
+        );
+    }
+
+    #[test]
+    fn english_auction_outbid_and_settle() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let end_time = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::CreateAuction {
+                reserve_price: Uint128::new(500),
+                min_increment_bps: 1000,
+                start_time: None,
+                end_time,
+                extension_secs: 10,
+                payment: Denom::Native(NATIVE_DENOM.to_string()),
+            },
+        );
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, marketplace_contract.addr().to_string());
+
+        //the seller cannot bid on its own auction
+        let err = place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            USER,
+            TOKEN_ID.to_string(),
+            300,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SellerCannotBid {}));
+
+        place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            300,
+        )
+        .unwrap();
+
+        //outbidding requires the minimum increment
+        let err = place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BIDDER,
+            TOKEN_ID.to_string(),
+            320,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BidTooLow { min } if min == Uint128::new(330)));
+
+        //a higher bid refunds the previous top bidder
+        place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BIDDER,
+            TOKEN_ID.to_string(),
+            600,
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_all_balances(BUYER).unwrap(),
+            vec![coin(10000, NATIVE_DENOM)]
+        );
+        let auction = get_auction(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .auction
+        .unwrap();
+        let highest_bid = auction.highest_bid.unwrap();
+        assert_eq!(highest_bid.bidder, BIDDER.to_string());
+        assert_eq!(highest_bid.amount, Uint128::new(600));
+        assert_eq!(auction.end_time, end_time);
+
+        let err = settle_auction(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotEnded {}));
+        let active = get_auctions(
+            &app,
+            &marketplace_contract,
+            QueryMsg::ActiveAuctions {
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert_eq!(active.auctions.len(), 1);
+        let ended = get_auctions(
+            &app,
+            &marketplace_contract,
+            QueryMsg::EndedAuctions {
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert!(ended.auctions.is_empty());
+
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        let active = get_auctions(
+            &app,
+            &marketplace_contract,
+            QueryMsg::ActiveAuctions {
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert!(active.auctions.is_empty());
+        let ended = get_auctions(
+            &app,
+            &marketplace_contract,
+            QueryMsg::EndedAuctions {
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert_eq!(ended.auctions.len(), 1);
+
+        let err = place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            1000,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AuctionEnded {}));
+
+        //the reserve is met, anyone can settle
+        settle_auction(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BIDDER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10600, NATIVE_DENOM)]
+        );
+        let auction = get_auction(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(auction.auction, None);
+    }
+
+    #[test]
+    fn auction_bid_near_end_extends_auction() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let end_time = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::CreateAuction {
+                reserve_price: Uint128::new(100),
+                min_increment_bps: 500,
+                start_time: None,
+                end_time,
+                extension_secs: 60,
+                payment: Denom::Native(NATIVE_DENOM.to_string()),
+            },
+        );
+
+        //a bid in the last 60 seconds pushes the end time back
+        app.update_block(|block| block.time = block.time.plus_seconds(90));
+        place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            200,
+        )
+        .unwrap();
+        let auction = get_auction(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .auction
+        .unwrap();
+        let extended_end = app.block_info().time.plus_seconds(60);
+        assert_eq!(auction.end_time, extended_end);
+
+        //bidding is still open after the original end
+        app.update_block(|block| block.time = block.time.plus_seconds(30));
+        place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BIDDER,
+            TOKEN_ID.to_string(),
+            210,
+        )
+        .unwrap();
+        let err = settle_auction(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotEnded {}));
+
+        app.update_block(|block| block.time = block.time.plus_seconds(60));
+        settle_auction(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BIDDER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10210, NATIVE_DENOM)]
+        );
+    }
+
+    #[test]
+    fn cw20_auction_below_reserve_returns_nft() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let end_time = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::CreateAuction {
+                reserve_price: Uint128::new(1000),
+                min_increment_bps: 0,
+                start_time: None,
+                end_time,
+                extension_secs: 0,
+                payment: Denom::Cw20(cw20_contract.addr()),
+            },
+        );
+
+        //native funds are refused on a cw20 auction
+        let err = place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            TOKEN_ID.to_string(),
+            400,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::NativePaymentForCw20Ask { cw20_contract: token }
+                if token == cw20_contract.addr().as_str()
+        ));
+
+        let hook_msg = Cw20HookMsg::PlaceAuctionBid {
+            collection: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+        };
+        let msg = Cw20ExecuteMsg::Send {
+            contract: marketplace_contract.addr().to_string(),
+            amount: Uint128::new(400),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        let cosmos_msg = cw20_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(BUYER), cosmos_msg).unwrap();
+        let buyer_balance = get_balance(&app, &cw20_contract, BUYER.to_string());
+        assert_eq!(buyer_balance.balance, Uint128::new(9600));
+
+        //the reserve was not met, the NFT goes back and the bid is refunded
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        settle_auction(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, USER.to_string());
+        let buyer_balance = get_balance(&app, &cw20_contract, BUYER.to_string());
+        assert_eq!(buyer_balance.balance, Uint128::new(10000));
+        let seller_balance = get_balance(&app, &cw20_contract, USER.to_string());
+        assert_eq!(seller_balance.balance, Uint128::new(10000));
+    }
+
+    #[test]
+    fn purchase_dutch_listing_at_block_time_price() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let end_time = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::SetDutchListing {
+                cw20_contract: None,
+                native_denom: None,
+                start_price: Uint128::new(1000),
+                floor_price: Uint128::new(200),
+                start_time: None,
+                end_time,
+                step_secs: None,
+                expires_at: None,
+                payout_address: None,
+            },
+        );
+        let price = get_current_price(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(price.price, Some(Uint128::new(1000)));
+
+        //halfway through the price is halfway to the floor
+        app.update_block(|block| block.time = block.time.plus_seconds(50));
+        let price = get_current_price(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(price.price, Some(Uint128::new(600)));
+
+        //declining asks are left out of the floor and the price sorted asks
+        let floor: FloorPriceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::FloorPrice {
+                    collection: cw721_contract.addr().to_string(),
+                    payment_token: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(floor.price, None);
+        let by_price: AsksByPriceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::AsksByPrice {
+                    collection: cw721_contract.addr().to_string(),
+                    payment_token: None,
+                    min: None,
+                    max: None,
+                    order: None,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert!(by_price.asks.is_empty());
+
+        let purchase_msg = ExecuteMsg::PurchaseNative {
+            collection: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &purchase_msg,
+                &coins(500, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        match err.downcast().unwrap() {
+            ContractError::WrongPaymentAmount { expected, received } => {
+                assert_eq!(expected, Uint128::new(600));
+                assert_eq!(received, Uint128::new(500));
+            }
+            err => panic!("unexpected error {}", err),
+        }
+
+        //paying above the current price refunds the difference
+        app.execute_contract(
+            Addr::unchecked(BUYER),
+            marketplace_contract.addr(),
+            &purchase_msg,
+            &coins(700, NATIVE_DENOM),
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(BUYER).unwrap(),
+            vec![coin(9400, NATIVE_DENOM)]
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10600, NATIVE_DENOM)]
+        );
+        let price = get_current_price(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(price.price, None);
+    }
+
+    #[test]
+    fn purchase_stepwise_dutch_listing_with_cw20() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let end_time = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::SetDutchListing {
+                cw20_contract: Some(cw20_contract.addr().to_string()),
+                native_denom: None,
+                start_price: Uint128::new(1000),
+                floor_price: Uint128::new(200),
+                start_time: None,
+                end_time,
+                step_secs: Some(30),
+                expires_at: Some(Expiration::AtTime(end_time.plus_seconds(100))),
+                payout_address: None,
+            },
+        );
+        let ask = get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .ask
+        .unwrap();
+        assert_eq!(
+            ask.expires_at,
+            Expiration::AtTime(end_time.plus_seconds(100))
+        );
+
+        //the price only drops at the end of each 30 second step
+        app.update_block(|block| block.time = block.time.plus_seconds(50));
+        let price = get_current_price(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(price.price, Some(Uint128::new(760)));
+
+        let hook_msg = Cw20HookMsg::Purchase {
+            cw721_contract: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+        };
+        let msg = Cw20ExecuteMsg::Send {
+            contract: marketplace_contract.addr().to_string(),
+            amount: Uint128::new(800),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        let cosmos_msg = cw20_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(BUYER), cosmos_msg).unwrap();
+
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        let buyer_balance = get_balance(&app, &cw20_contract, BUYER.to_string());
+        assert_eq!(buyer_balance.balance, Uint128::new(9240));
+        let seller_balance = get_balance(&app, &cw20_contract, USER.to_string());
+        assert_eq!(seller_balance.balance, Uint128::new(10760));
+    }
+
+    #[test]
+    fn sealed_auction_highest_reveal_wins() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let commit_end = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::CreateSealedAuction {
+                reserve_price: Uint128::new(100),
+                commit_end,
+                reveal_end: commit_end.plus_seconds(100),
+                payment: Denom::Native(NATIVE_DENOM.to_string()),
+            },
+        );
+
+        //collateral hides the amount bid
+        commit_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            500,
+            "buyer salt",
+            800,
+        )
+        .unwrap();
+        commit_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BIDDER,
+            600,
+            "bidder salt",
+            700,
+        )
+        .unwrap();
+        let err = commit_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BIDDER,
+            650,
+            "bidder salt",
+            700,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyCommitted {}));
+        let err = reveal_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            500,
+            "buyer salt",
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotRevealPhase {}));
+
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        let err = commit_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            USER,
+            100,
+            "late salt",
+            100,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CommitPhaseEnded {}));
+
+        //a reveal must match the commitment
+        let err = reveal_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            500,
+            "wrong salt",
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReveal {}));
+        reveal_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            500,
+            "buyer salt",
+        )
+        .unwrap();
+
+        //the higher reveal refunds the previous leader
+        reveal_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BIDDER,
+            600,
+            "bidder salt",
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_all_balances(BUYER).unwrap(),
+            vec![coin(10000, NATIVE_DENOM)]
+        );
+        let auction: SealedAuctionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::SealedAuction {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id: TOKEN_ID.to_string(),
+                },
+            )
+            .unwrap();
+        let highest_bid = auction.auction.unwrap().highest_bid.unwrap();
+        assert_eq!(highest_bid.bidder, BIDDER.to_string());
+        assert_eq!(highest_bid.amount, Uint128::new(600));
+
+        let err =
+            settle_sealed_auction(&mut app, &marketplace_contract, &cw721_contract).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotEnded {}));
+
+        //the winner pays the revealed amount and gets the rest of the collateral back
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        settle_sealed_auction(&mut app, &marketplace_contract, &cw721_contract).unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BIDDER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(BIDDER).unwrap(),
+            vec![coin(9400, NATIVE_DENOM)]
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10600, NATIVE_DENOM)]
+        );
+    }
+
+    #[test]
+    fn sealed_auction_settles_with_the_maximum_of_unrevealed_bids() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let commit_end = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::CreateSealedAuction {
+                reserve_price: Uint128::new(100),
+                commit_end,
+                reveal_end: commit_end.plus_seconds(100),
+                payment: Denom::Native(NATIVE_DENOM.to_string()),
+            },
+        );
+
+        //collateral below the reserve could never win
+        let err = commit_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            1,
+            "buyer salt",
+            1,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BidTooLow { min } if min == Uint128::new(100)));
+
+        //the auction takes up to 100 commitments, none of them revealed
+        let bidders: Vec<String> = (0..100).map(|i| format!("bidder{:03}", i)).collect();
+        app.init_modules(|router, _, storage| {
+            for bidder in &bidders {
+                router
+                    .bank
+                    .init_balance(
+                        storage,
+                        &Addr::unchecked(bidder),
+                        vec![coin(100, NATIVE_DENOM)],
+                    )
+                    .unwrap();
+            }
+        });
+        for bidder in &bidders {
+            commit_sealed_bid(
+                &mut app,
+                &marketplace_contract,
+                &cw721_contract,
+                bidder,
+                100,
+                "salt",
+                100,
+            )
+            .unwrap();
+        }
+        let err = commit_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            100,
+            "buyer salt",
+            100,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TooManySealedBids { max: 100 }));
+
+        //settling slashes every unrevealed bid to the seller and returns the NFT
+        app.update_block(|block| block.time = block.time.plus_seconds(200));
+        settle_sealed_auction(&mut app, &marketplace_contract, &cw721_contract).unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, USER.to_string());
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(20000, NATIVE_DENOM)]
+        );
+    }
+
+    #[test]
+    fn sealed_auction_slashes_unrevealed_cw20_collateral() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let commit_end = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::CreateSealedAuction {
+                reserve_price: Uint128::new(100),
+                commit_end,
+                reveal_end: commit_end.plus_seconds(100),
+                payment: Denom::Cw20(cw20_contract.addr()),
+            },
+        );
+
+        //native collateral is refused on a cw20 auction
+        let err = commit_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            300,
+            "salt",
+            400,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::NativePaymentForCw20Ask { cw20_contract: token }
+                if token == cw20_contract.addr().as_str()
+        ));
+
+        for (bidder, amount, collateral) in [(BUYER, 300, 400), (BIDDER, 450, 500)] {
+            let hook_msg = Cw20HookMsg::CommitSealedBid {
+                collection: cw721_contract.addr().to_string(),
+                token_id: TOKEN_ID.to_string(),
+                commitment: sealed_bid_commitment(
+                    cw721_contract.addr().as_str(),
+                    TOKEN_ID,
+                    bidder,
+                    Uint128::new(amount),
+                    "salt",
+                ),
+            };
+            let msg = Cw20ExecuteMsg::Send {
+                contract: marketplace_contract.addr().to_string(),
+                amount: Uint128::new(collateral),
+                msg: to_binary(&hook_msg).unwrap(),
+            };
+            let cosmos_msg = cw20_contract.call(msg).unwrap();
+            app.execute(Addr::unchecked(bidder), cosmos_msg).unwrap();
+        }
+        let bids: SealedBidsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::SealedBids {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id: TOKEN_ID.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(bids.bids.len(), 2);
+        assert!(bids.bids.iter().all(|bid| bid.revealed.is_none()));
+
+        //only the buyer reveals
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        reveal_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            300,
+            "salt",
+        )
+        .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        let err = reveal_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BIDDER,
+            450,
+            "salt",
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotRevealPhase {}));
+
+        //the seller receives the winning bid and the unrevealed collateral
+        settle_sealed_auction(&mut app, &marketplace_contract, &cw721_contract).unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        let buyer_balance = get_balance(&app, &cw20_contract, BUYER.to_string());
+        assert_eq!(buyer_balance.balance, Uint128::new(9700));
+        let bidder_balance = get_balance(&app, &cw20_contract, BIDDER.to_string());
+        assert_eq!(bidder_balance.balance, Uint128::new(9500));
+        let seller_balance = get_balance(&app, &cw20_contract, USER.to_string());
+        assert_eq!(seller_balance.balance, Uint128::new(10800));
+    }
+
+    #[test]
+    fn trading_fee_split_on_purchases() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate_with_fee(&mut app, marketplace_id, 250);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        for token_id in ["0", "1"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "0".to_string(),
+            400,
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            Some(&cw20_contract),
+            "1".to_string(),
+            500,
+        );
+
+        //2.5% of each sale goes to the fee recipient
+        buy_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "0".to_string(),
+            400,
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10390, NATIVE_DENOM)]
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(TREASURY).unwrap(),
+            vec![coin(10, NATIVE_DENOM)]
+        );
+
+        buy_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            Some(&cw20_contract),
+            "1".to_string(),
+            500,
+        );
+        let seller_balance = get_balance(&app, &cw20_contract, USER.to_string());
+        assert_eq!(seller_balance.balance, Uint128::new(10488));
+        let treasury_balance = get_balance(&app, &cw20_contract, TREASURY.to_string());
+        assert_eq!(treasury_balance.balance, Uint128::new(12));
+    }
+
+    #[test]
+    fn only_admin_updates_config() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let update_msg = ExecuteMsg::UpdateConfig {
+            native_denoms: None,
+            trading_fee_bps: Some(1000),
+            fee_recipient: Some(BIDDER.to_string()),
+            max_royalty_bps: None,
+            collection_mode: None,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER),
+                marketplace_contract.addr(),
+                &update_msg,
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::Unauthorized {}
+        ));
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                marketplace_contract.addr(),
+                &ExecuteMsg::UpdateConfig {
+                    native_denoms: None,
+                    trading_fee_bps: Some(9_001),
+                    fee_recipient: None,
+                    max_royalty_bps: None,
+                    collection_mode: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::InvalidTradingFee {}
+        ));
+        //a fee large enough to overflow the sum is rejected rather than panicking
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                marketplace_contract.addr(),
+                &ExecuteMsg::UpdateConfig {
+                    native_denoms: None,
+                    trading_fee_bps: Some(u64::MAX),
+                    fee_recipient: None,
+                    max_royalty_bps: None,
+                    collection_mode: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::InvalidTradingFee {}
+        ));
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &update_msg,
+            &[],
+        )
+        .unwrap();
+
+        //the new fee applies to the next sale
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            TOKEN_ID.to_string(),
+            500,
+        );
+        buy_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            TOKEN_ID.to_string(),
+            500,
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10450, NATIVE_DENOM)]
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(BIDDER).unwrap(),
+            vec![coin(10050, NATIVE_DENOM)]
+        );
+    }
+
+    #[test]
+    fn registry_royalty_paid_on_purchases() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate_with_fee(&mut app, marketplace_id, 250);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+
+        //only the collection minter registers its royalty, up to the cap
+        let royalty_msg = |share_bps| ExecuteMsg::SetRoyalty {
+            collection: cw721_contract.addr().to_string(),
+            recipient: BIDDER.to_string(),
+            share_bps,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &royalty_msg(500),
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::NotCollectionMinter {}
+        ));
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER),
+                marketplace_contract.addr(),
+                &royalty_msg(1500),
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::InvalidRoyalty { max_bps: 1000 }
+        ));
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &royalty_msg(500),
+            &[],
+        )
+        .unwrap();
+        let royalty: RoyaltyResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::Royalty {
+                    collection: cw721_contract.addr().to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(royalty.royalty.unwrap().share_bps, 500);
+
+        for token_id in ["0", "1"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "0".to_string(),
+            400,
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            Some(&cw20_contract),
+            "1".to_string(),
+            500,
+        );
+
+        //the price is split between seller, fee recipient and royalty recipient
+        buy_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "0".to_string(),
+            400,
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(10370, NATIVE_DENOM)]
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(TREASURY).unwrap(),
+            vec![coin(10, NATIVE_DENOM)]
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(BIDDER).unwrap(),
+            vec![coin(10020, NATIVE_DENOM)]
+        );
+
+        buy_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            Some(&cw20_contract),
+            "1".to_string(),
+            500,
+        );
+        let seller_balance = get_balance(&app, &cw20_contract, USER.to_string());
+        assert_eq!(seller_balance.balance, Uint128::new(10463));
+        let treasury_balance = get_balance(&app, &cw20_contract, TREASURY.to_string());
+        assert_eq!(treasury_balance.balance, Uint128::new(12));
+        let royalty_balance = get_balance(&app, &cw20_contract, BIDDER.to_string());
+        assert_eq!(royalty_balance.balance, Uint128::new(10025));
+    }
+
+    #[test]
+    fn cw2981_royalty_from_token_metadata() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let mint_msg = cw721_base::MintMsg {
+            token_id: TOKEN_ID.to_string(),
+            owner: USER.to_string(),
+            token_uri: None,
+            extension: Some(Metadata {
+                royalty_percentage: Some(5),
+                royalty_payment_address: Some(BIDDER.to_string()),
+                ..Metadata::default()
+            }),
+        };
+        let cosmos_msg = cw721_contract
+            .call(nft::contract::Cw721ExecuteMsg::Mint(mint_msg))
+            .unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        //the registry is only consulted when the collection reports no royalty
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::SetRoyalty {
+                collection: cw721_contract.addr().to_string(),
+                recipient: TREASURY.to_string(),
+                share_bps: 1000,
+            },
+            &[],
+        )
+        .unwrap();
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            TOKEN_ID.to_string(),
+            400,
+        );
+        buy_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            TOKEN_ID.to_string(),
+            400,
+        );
+
+        let balance = |address: &str| {
+            app.wrap()
+                .query_balance(address, NATIVE_DENOM)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(balance(USER), Uint128::new(10380));
+        assert_eq!(balance(BIDDER), Uint128::new(10020));
+        assert_eq!(balance(TREASURY), Uint128::zero());
+    }
+
+    #[test]
+    fn two_step_admin_transfer_and_renounce() {
+        let (mut app, marketplace_id, _, _) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let get_config = |app: &App| {
+            let res: ConfigResponse = app
+                .wrap()
+                .query_wasm_smart(marketplace_contract.addr(), &QueryMsg::Config {})
+                .unwrap();
+            res.config
+        };
+        let execute = |app: &mut App, sender: &str, msg: &ExecuteMsg| {
+            app.execute_contract(
+                Addr::unchecked(sender),
+                marketplace_contract.addr(),
+                msg,
+                &[],
+            )
+            .map_err(|err| err.downcast::<ContractError>().unwrap())
+        };
+        assert_eq!(get_config(&app).admin, Some(Addr::unchecked(ADMIN)));
+
+        let propose_msg = ExecuteMsg::ProposeNewAdmin {
+            admin: USER.to_string(),
+        };
+        let err = execute(&mut app, USER, &propose_msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(&mut app, ADMIN, &propose_msg).unwrap();
+        let config = get_config(&app);
+        assert_eq!(config.admin, Some(Addr::unchecked(ADMIN)));
+        assert_eq!(config.pending_admin, Some(Addr::unchecked(USER)));
+
+        //only the proposed address can accept
+        let err = execute(&mut app, BIDDER, &ExecuteMsg::AcceptAdmin {}).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(&mut app, USER, &ExecuteMsg::AcceptAdmin {}).unwrap();
+        let config = get_config(&app);
+        assert_eq!(config.admin, Some(Addr::unchecked(USER)));
+        assert_eq!(config.pending_admin, None);
+
+        let update_msg = ExecuteMsg::UpdateConfig {
+            native_denoms: None,
+            trading_fee_bps: Some(100),
+            fee_recipient: None,
+            max_royalty_bps: Some(500),
+            collection_mode: None,
+        };
+        let err = execute(&mut app, ADMIN, &update_msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(&mut app, USER, &update_msg).unwrap();
+        let config = get_config(&app);
+        assert_eq!(config.trading_fee_bps, 100);
+        assert_eq!(config.max_royalty_bps, 500);
+
+        //once renounced nobody can change the config
+        execute(&mut app, USER, &ExecuteMsg::RenounceAdmin {}).unwrap();
+        assert_eq!(get_config(&app).admin, None);
+        let err = execute(&mut app, USER, &update_msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(&mut app, USER, &propose_msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn paused_operations_fail_but_listings_can_be_removed() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        for token_id in ["0", "1"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "0".to_string(),
+            500,
+        );
+        set_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BIDDER,
+            "1".to_string(),
+            100,
+        );
+
+        let pause_msg = ExecuteMsg::UpdatePauseStatus {
+            listing: Some(true),
+            purchasing: Some(true),
+            bidding: Some(true),
+            withdrawals: Some(true),
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER),
+                marketplace_contract.addr(),
+                &pause_msg,
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::Unauthorized {}
+        ));
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &pause_msg,
+            &[],
+        )
+        .unwrap();
+        let res: PauseStatusResponse = app
+            .wrap()
+            .query_wasm_smart(marketplace_contract.addr(), &QueryMsg::PauseStatus {})
+            .unwrap();
+        assert!(res.pause_status.listing && res.pause_status.withdrawals);
+
+        let hook_msg = Cw721HookMsg::SetListing {
+            cw20_contract: None,
+            native_denom: None,
+            amount: 500,
+            expires_at: None,
+            payout_address: None,
+        };
+        let send_msg = nft::contract::Cw721ExecuteMsg::SendNft {
+            contract: marketplace_contract.addr().to_string(),
+            token_id: "1".to_string(),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        let err = app
+            .execute(
+                Addr::unchecked(USER),
+                cw721_contract.call(send_msg).unwrap(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::Paused { operation }) if operation == "listing"
+        ));
+
+        let paused_calls = [
+            (
+                ExecuteMsg::PurchaseNative {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id: "0".to_string(),
+                },
+                coins(500, NATIVE_DENOM),
+                "purchasing",
+            ),
+            (
+                ExecuteMsg::SetBid {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id: "0".to_string(),
+                    expires: None,
+                },
+                coins(100, NATIVE_DENOM),
+                "bidding",
+            ),
+            (
+                ExecuteMsg::ReturnExpiredListings { limit: None },
+                vec![],
+                "withdrawals",
+            ),
+        ];
+        for (msg, funds, paused_operation) in paused_calls {
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(BIDDER),
+                    marketplace_contract.addr(),
+                    &msg,
+                    &funds,
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::Paused { operation } if operation == paused_operation
+            ));
+        }
+
+        //sellers can always take their NFT back
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::RemoveListing {
+                collection: cw721_contract.addr().to_string(),
+                token_id: "0".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+
+        //and bidders their escrow
+        app.execute_contract(
+            Addr::unchecked(BIDDER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::RemoveBid {
+                collection: cw721_contract.addr().to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_all_balances(BIDDER).unwrap(),
+            vec![coin(10000, NATIVE_DENOM)]
+        );
+    }
+
+    #[test]
+    fn collection_allowlist_and_denylist() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let collection_msg = |blocked| ExecuteMsg::SetCollection {
+            address: cw721_contract.addr().to_string(),
+            name: "NFT".to_string(),
+            verified: true,
+            blocked,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER),
+                marketplace_contract.addr(),
+                &collection_msg(true),
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::Unauthorized {}
+        ));
+
+        //a blocked collection cannot be listed in open mode
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &collection_msg(true),
+            &[],
+        )
+        .unwrap();
+        let hook_msg = Cw721HookMsg::SetListing {
+            cw20_contract: None,
+            native_denom: None,
+            amount: 500,
+            expires_at: None,
+            payout_address: None,
+        };
+        let send_msg = nft::contract::Cw721ExecuteMsg::SendNft {
+            contract: marketplace_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        let err = app
+            .execute(
+                Addr::unchecked(USER),
+                cw721_contract.call(send_msg).unwrap(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::CollectionNotAllowed {})
+        ));
+
+        //in allowlist mode only registered collections trade
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &ExecuteMsg::UpdateConfig {
+                native_denoms: None,
+                trading_fee_bps: None,
+                fee_recipient: None,
+                max_royalty_bps: None,
+                collection_mode: Some(CollectionMode::Allowlist),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &collection_msg(false),
+            &[],
+        )
+        .unwrap();
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            TOKEN_ID.to_string(),
+            500,
+        );
+        let res: CollectionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::Collection {
+                    address: cw721_contract.addr().to_string(),
+                },
+            )
+            .unwrap();
+        let collection = res.collection.unwrap();
+        assert!(collection.verified && !collection.blocked);
+        let res: CollectionsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::Collections {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.collections, vec![collection]);
+        assert_eq!(res.next_start_after, None);
+
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &ExecuteMsg::RemoveCollection {
+                address: cw721_contract.addr().to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &ExecuteMsg::PurchaseNative {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id: TOKEN_ID.to_string(),
+                },
+                &coins(500, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::CollectionNotAllowed {}
+        ));
+    }
+
+    #[test]
+    fn blocked_collection_auctions_refuse_bids() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        for token_id in [TOKEN_ID, "2"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        let end_time = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            Cw721HookMsg::CreateSealedAuction {
+                reserve_price: Uint128::new(100),
+                commit_end: end_time,
+                reveal_end: end_time.plus_seconds(100),
+                payment: Denom::Native(NATIVE_DENOM.to_string()),
+            },
+        );
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            "2".to_string(),
+            Cw721HookMsg::CreateAuction {
+                reserve_price: Uint128::new(100),
+                min_increment_bps: 0,
+                start_time: None,
+                end_time,
+                extension_secs: 0,
+                payment: Denom::Native(NATIVE_DENOM.to_string()),
+            },
+        );
+
+        //blocking the collection stops new bids on its running auctions
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &ExecuteMsg::SetCollection {
+                address: cw721_contract.addr().to_string(),
+                name: "NFT".to_string(),
+                verified: false,
+                blocked: true,
+            },
+            &[],
+        )
+        .unwrap();
+        let err = place_auction_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            "2".to_string(),
+            100,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CollectionNotAllowed {}));
+        let err = commit_sealed_bid(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            BUYER,
+            100,
+            "buyer salt",
+            100,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CollectionNotAllowed {}));
+
+        //settling still hands the NFTs back to the seller
+        app.update_block(|block| block.time = block.time.plus_seconds(200));
+        settle_auction(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            "2".to_string(),
+        )
+        .unwrap();
+        settle_sealed_auction(&mut app, &marketplace_contract, &cw721_contract).unwrap();
+        for token_id in [TOKEN_ID, "2"] {
+            let owner = get_owner_of(&app, &cw721_contract, token_id.to_string());
+            assert_eq!(owner.owner, USER.to_string());
+        }
+    }
+
+    #[test]
+    fn only_allowed_cw20_tokens_can_price_listings() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        let hook_msg = Cw721HookMsg::SetListing {
+            cw20_contract: Some(cw20_contract.addr().to_string()),
+            native_denom: None,
+            amount: 500,
+            expires_at: None,
+            payout_address: None,
+        };
+        let send_msg = nft::contract::Cw721ExecuteMsg::SendNft {
+            contract: marketplace_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        let err = app
+            .execute(
+                Addr::unchecked(USER),
+                cw721_contract.call(send_msg).unwrap(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::PaymentTokenNotAllowed {})
+        ));
+
+        //only the admin adds tokens, and only actual cw20 contracts
+        let add_msg = |address: &Addr| ExecuteMsg::AddPaymentToken {
+            address: address.to_string(),
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER),
+                marketplace_contract.addr(),
+                &add_msg(&cw20_contract.addr()),
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::Unauthorized {}
+        ));
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &add_msg(&cw721_contract.addr()),
+            &[],
+        )
+        .unwrap_err();
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        let res: PaymentTokensResponse = app
+            .wrap()
+            .query_wasm_smart(marketplace_contract.addr(), &QueryMsg::PaymentTokens {})
+            .unwrap();
+        assert_eq!(res.tokens.len(), 1);
+        assert_eq!(res.tokens[0].address, cw20_contract.addr().to_string());
+        assert_eq!(res.tokens[0].symbol, "TKN");
+        assert_eq!(res.tokens[0].decimals, 10);
+
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            Some(&cw20_contract),
+            TOKEN_ID.to_string(),
+            500,
+        );
+        let ask = get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        );
+        assert_eq!(ask.ask.unwrap().payment, Denom::Cw20(cw20_contract.addr()));
+
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &ExecuteMsg::RemovePaymentToken {
+                address: cw20_contract.addr().to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        let res: PaymentTokensResponse = app
+            .wrap()
+            .query_wasm_smart(marketplace_contract.addr(), &QueryMsg::PaymentTokens {})
+            .unwrap();
+        assert!(res.tokens.is_empty());
+    }
+
+    #[test]
+    fn purchase_must_pay_in_ask_currency() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        let other_cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        for token_id in ["0", "1"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        //"0" is priced in the cw20, "1" in the native denom
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            Some(&cw20_contract),
+            "0".to_string(),
+            500,
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "1".to_string(),
+            500,
+        );
+        let cw20_purchase = |app: &mut App, cw20_token: &Cw20Contract, token_id: &str| {
+            let hook_msg = Cw20HookMsg::Purchase {
+                cw721_contract: cw721_contract.addr().to_string(),
+                token_id: token_id.to_string(),
+            };
+            let msg = Cw20ExecuteMsg::Send {
+                contract: marketplace_contract.addr().to_string(),
+                amount: Uint128::new(500),
+                msg: to_binary(&hook_msg).unwrap(),
+            };
+            app.execute(Addr::unchecked(BUYER), cw20_token.call(msg).unwrap())
+                .unwrap_err()
+        };
+
+        //a cw20 ask cannot be paid with another cw20 of the same nominal amount
+        let err = cw20_purchase(&mut app, &other_cw20_contract, "0");
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::WrongPaymentToken { expected, received })
+                if cw20_contract.addr() == *expected
+                    && other_cw20_contract.addr() == *received
+        ));
+
+        //nor with native coins
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &ExecuteMsg::PurchaseNative {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id: "0".to_string(),
+                },
+                &coins(500, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::NativePaymentForCw20Ask { cw20_contract: token }
+                if token == cw20_contract.addr()
+        ));
+
+        //and a native ask cannot be paid with a cw20
+        let err = cw20_purchase(&mut app, &cw20_contract, "1");
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::WrongPaymentToken { expected, .. }) if expected == NATIVE_DENOM
+        ));
+
+        for token_id in ["0", "1"] {
+            let owner = get_owner_of(&app, &cw721_contract, token_id.to_string());
+            assert_eq!(owner.owner, marketplace_contract.addr().to_string());
+        }
+        let buyer_balance = get_balance(&app, &cw20_contract, BUYER.to_string());
+        assert_eq!(buyer_balance.balance, Uint128::new(10000));
+        let buyer_balance = get_balance(&app, &other_cw20_contract, BUYER.to_string());
+        assert_eq!(buyer_balance.balance, Uint128::new(10000));
+    }
+
+    #[test]
+    fn asks_priced_in_further_native_denoms() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        app.init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(BUYER),
+                    vec![coin(10000, USDC_DENOM), coin(10000, NATIVE_DENOM)],
+                )
+                .unwrap()
+        });
+        for token_id in ["0", "1"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        let list_in_usdc = |app: &mut App| {
+            let hook_msg = Cw721HookMsg::SetListing {
+                cw20_contract: None,
+                native_denom: Some(USDC_DENOM.to_string()),
+                amount: 300,
+                expires_at: None,
+                payout_address: None,
+            };
+            let msg = nft::contract::Cw721ExecuteMsg::SendNft {
+                contract: marketplace_contract.addr().to_string(),
+                token_id: "0".to_string(),
+                msg: to_binary(&hook_msg).unwrap(),
+            };
+            app.execute(Addr::unchecked(USER), cw721_contract.call(msg).unwrap())
+        };
+        let err = list_in_usdc(&mut app).unwrap_err();
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::DenomNotAllowed { denom }) if denom == USDC_DENOM
+        ));
+
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            marketplace_contract.addr(),
+            &ExecuteMsg::UpdateConfig {
+                native_denoms: Some(vec![USDC_DENOM.to_string()]),
+                trading_fee_bps: None,
+                fee_recipient: None,
+                max_royalty_bps: None,
+                collection_mode: None,
+            },
+            &[],
+        )
+        .unwrap();
+        list_in_usdc(&mut app).unwrap();
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "1".to_string(),
+            500,
+        );
+
+        //queries filter by the denom, the default native denom if none is given
+        let asks_in = |app: &App, payment_token: Option<&str>| {
+            let res: AsksResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    marketplace_contract.addr(),
+                    &QueryMsg::AsksByPaymentToken {
+                        payment_token: payment_token.map(str::to_string),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            res.asks
+                .into_iter()
+                .map(|ask| ask.token_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(asks_in(&app, Some(USDC_DENOM)), vec!["0".to_string()]);
+        assert_eq!(asks_in(&app, None), vec!["1".to_string()]);
+        assert_eq!(asks_in(&app, Some(NATIVE_DENOM)), vec!["1".to_string()]);
+        let floor: FloorPriceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                marketplace_contract.addr(),
+                &QueryMsg::FloorPrice {
+                    collection: cw721_contract.addr().to_string(),
+                    payment_token: Some(USDC_DENOM.to_string()),
+                },
+            )
+            .unwrap();
+        assert_eq!(floor.price, Some(Uint128::new(300)));
+
+        //the ask's own denom has to be paid
+        let purchase_msg = ExecuteMsg::PurchaseNative {
+            collection: cw721_contract.addr().to_string(),
+            token_id: "0".to_string(),
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &purchase_msg,
+                &coins(300, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert!(matches!(err.downcast().unwrap(), ContractError::Payment(_)));
+        app.execute_contract(
+            Addr::unchecked(BUYER),
+            marketplace_contract.addr(),
+            &purchase_msg,
+            &coins(300, USDC_DENOM),
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        assert_eq!(
+            app.wrap().query_balance(USER, USDC_DENOM).unwrap().amount,
+            Uint128::new(300)
+        );
+    }
+
+    #[test]
+    fn expired_listings_are_hidden_and_returned() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        for token_id in ["0", "1", "2", "3"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        let list_until = |app: &mut App, token_id: &str, expires_at: Expiration| {
+            let hook_msg = Cw721HookMsg::SetListing {
+                cw20_contract: None,
+                native_denom: None,
+                amount: 500,
+                expires_at: Some(expires_at),
+                payout_address: None,
+            };
+            let msg = nft::contract::Cw721ExecuteMsg::SendNft {
+                contract: marketplace_contract.addr().to_string(),
+                token_id: token_id.to_string(),
+                msg: to_binary(&hook_msg).unwrap(),
+            };
+            app.execute(Addr::unchecked(USER), cw721_contract.call(msg).unwrap())
+        };
+        let now = app.block_info().time;
+        let err = list_until(&mut app, "2", Expiration::AtTime(now)).unwrap_err();
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::InvalidExpiration {})
+        ));
+        list_until(&mut app, "0", Expiration::AtTime(now.plus_seconds(100))).unwrap();
+        let height = app.block_info().height;
+        list_until(&mut app, "3", Expiration::AtHeight(height + 10)).unwrap();
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "1".to_string(),
+            500,
+        );
+        assert_eq!(
+            get_all_asks(&app, &marketplace_contract, None, None)
+                .asks
+                .len(),
+            3
+        );
+
+        app.update_block(|block| {
+            block.time = block.time.plus_seconds(100);
+            block.height += 10;
+        });
+        let asks = get_all_asks(&app, &marketplace_contract, None, None).asks;
+        assert_eq!(asks.len(), 1);
+        assert_eq!(asks[0].1.token_id, "1".to_string());
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &ExecuteMsg::PurchaseNative {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id: "0".to_string(),
+                },
+                &coins(500, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::AskExpired {}
+        ));
+
+        //anyone can send expired NFTs back to their sellers, asks expiring at a height first
+        let return_expired = |app: &mut App, limit: u32| {
+            app.execute_contract(
+                Addr::unchecked(BIDDER),
+                marketplace_contract.addr(),
+                &ExecuteMsg::ReturnExpiredListings { limit: Some(limit) },
+                &[],
+            )
+            .unwrap()
+        };
+        return_expired(&mut app, 1);
+        let owner = get_owner_of(&app, &cw721_contract, "3".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, marketplace_contract.addr().to_string());
+        return_expired(&mut app, 10);
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+        let ask = get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            "0".to_string(),
+        );
+        assert_eq!(ask.ask, None);
+        let owner = get_owner_of(&app, &cw721_contract, "1".to_string());
+        assert_eq!(owner.owner, marketplace_contract.addr().to_string());
+    }
+
+    #[test]
+    fn seller_updates_ask_in_place() {
+        let (mut app, marketplace_id, cw20_id, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        allow_payment_token(&mut app, &marketplace_contract, &cw20_contract);
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+        list_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            TOKEN_ID.to_string(),
+            500,
+        );
+
+        let expires = Expiration::AtTime(app.block_info().time.plus_seconds(100));
+        let update_msg = ExecuteMsg::UpdateAsk {
+            collection: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            price: Some(Uint128::new(400)),
+            payment: Some(Denom::Cw20(cw20_contract.addr())),
+            expires: Some(expires),
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(BUYER),
+                marketplace_contract.addr(),
+                &update_msg,
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::UnauthorizedOwner {}
+        ));
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER),
+                marketplace_contract.addr(),
+                &update_msg,
+                &[],
+            )
+            .unwrap();
+        let event = res
+            .events
+            .iter()
+            .find(|event| event.ty == "wasm-update_ask")
+            .unwrap();
+        assert!(event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "price" && attr.value == "400"));
+
+        //the NFT stays in the marketplace and the indexes follow the new price and currency
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, marketplace_contract.addr().to_string());
+        let floor_in = |app: &App, payment_token: Option<String>| {
+            let res: FloorPriceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    marketplace_contract.addr(),
+                    &QueryMsg::FloorPrice {
+                        collection: cw721_contract.addr().to_string(),
+                        payment_token,
+                    },
+                )
+                .unwrap();
+            res.price
+        };
+        assert_eq!(floor_in(&app, None), None);
+        assert_eq!(
+            floor_in(&app, Some(cw20_contract.addr().to_string())),
+            Some(Uint128::new(400))
+        );
+        let ask = get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+        )
+        .ask
+        .unwrap();
+        assert_eq!(ask.expires_at, expires);
+
+        //once expired, the ask can only be changed together with a new expiration
+        app.update_block(|block| block.time = block.time.plus_seconds(101));
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER),
+                marketplace_contract.addr(),
+                &ExecuteMsg::UpdateAsk {
+                    collection: cw721_contract.addr().to_string(),
+                    token_id: TOKEN_ID.to_string(),
+                    price: Some(Uint128::new(300)),
+                    payment: None,
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::AskExpired {}
+        ));
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &ExecuteMsg::UpdateAsk {
+                collection: cw721_contract.addr().to_string(),
+                token_id: TOKEN_ID.to_string(),
+                price: None,
+                payment: None,
+                expires: Some(Expiration::Never {}),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let hook_msg = Cw20HookMsg::Purchase {
+            cw721_contract: cw721_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+        };
+        let msg = Cw20ExecuteMsg::Send {
+            contract: marketplace_contract.addr().to_string(),
+            amount: Uint128::new(400),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        app.execute(Addr::unchecked(BUYER), cw20_contract.call(msg).unwrap())
+            .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        let seller_balance = get_balance(&app, &cw20_contract, USER.to_string());
+        assert_eq!(seller_balance.balance, Uint128::new(10400));
+    }
+
+    #[test]
+    fn seller_is_the_sender_of_the_nft() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        for token_id in ["0", "1"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        let send_nft = |app: &mut App, token_id: &str, msg: Binary| {
+            let send_msg = nft::contract::Cw721ExecuteMsg::SendNft {
+                contract: marketplace_contract.addr().to_string(),
+                token_id: token_id.to_string(),
+                msg,
+            };
+            let cosmos_msg = cw721_contract.call(send_msg).unwrap();
+            app.execute(Addr::unchecked(USER), cosmos_msg)
+        };
+
+        //a hook claiming another owner and token cannot redirect the listing
+        let spoofed = format!(
+            r#"{{"set_listing":{{"owner":"{}","token_id":"1","amount":"500"}}}}"#,
+            BIDDER
+        );
+        send_nft(&mut app, "0", Binary::from(spoofed.as_bytes())).unwrap();
+        let ask = get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            "0".to_string(),
+        )
+        .ask
+        .unwrap();
+        assert_eq!(ask.seller, USER.to_string());
+        assert_eq!(ask.payee(), &Addr::unchecked(USER));
+        assert!(get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            "1".to_string()
+        )
+        .ask
+        .is_none());
+        let remove_msg = ExecuteMsg::RemoveListing {
+            collection: cw721_contract.addr().to_string(),
+            token_id: "0".to_string(),
+        };
+        app.execute_contract(
+            Addr::unchecked(BIDDER),
+            marketplace_contract.addr(),
+            &remove_msg,
+            &[],
+        )
+        .unwrap_err();
+        app.execute_contract(
+            Addr::unchecked(USER),
+            marketplace_contract.addr(),
+            &remove_msg,
+            &[],
+        )
+        .unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+
+        //an invalid payout address is rejected
+        let listing = |payout_address: &str| {
+            to_binary(&Cw721HookMsg::SetListing {
+                cw20_contract: None,
+                native_denom: None,
+                amount: 500,
+                expires_at: None,
+                payout_address: Some(payout_address.to_string()),
+            })
+            .unwrap()
+        };
+        let err = send_nft(&mut app, "1", listing("INVALID")).unwrap_err();
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::InvalidAddress { .. })
+        ));
+        let owner = get_owner_of(&app, &cw721_contract, "1".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+
+        //the proceeds go to the payout address, the seller keeps control of the listing
+        send_nft(&mut app, "1", listing(TREASURY)).unwrap();
+        let ask = get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            "1".to_string(),
+        )
+        .ask
+        .unwrap();
+        assert_eq!(ask.seller, USER.to_string());
+        assert_eq!(ask.payout_address, Some(Addr::unchecked(TREASURY)));
+        buy_nft(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            None,
+            "1".to_string(),
+            500,
+        );
+        let owner = get_owner_of(&app, &cw721_contract, "1".to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        let balance = |app: &App, address: &str| {
+            app.wrap()
+                .query_balance(address, NATIVE_DENOM)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(balance(&app, TREASURY), Uint128::new(500));
+        assert_eq!(balance(&app, USER), Uint128::new(10000));
+    }
+
+    #[test]
+    fn invalid_addresses_are_rejected() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        mint_nft(
+            &mut app,
+            &cw721_contract,
+            TOKEN_ID.to_string(),
+            None,
+            USER.to_string(),
+        );
+
+        let invalid_calls = [
+            (
+                ADMIN,
+                ExecuteMsg::UpdateConfig {
+                    native_denoms: None,
+                    trading_fee_bps: None,
+                    fee_recipient: Some("Treasury".to_string()),
+                    max_royalty_bps: None,
+                    collection_mode: None,
+                },
+                vec![],
+            ),
+            (
+                ADMIN,
+                ExecuteMsg::ProposeNewAdmin {
+                    admin: "x".to_string(),
+                },
+                vec![],
+            ),
+            (
+                BIDDER,
+                ExecuteMsg::SetBid {
+                    collection: "NOT_A_COLLECTION".to_string(),
+                    token_id: TOKEN_ID.to_string(),
+                    expires: None,
+                },
+                coins(100, NATIVE_DENOM),
+            ),
+        ];
+        for (sender, msg, funds) in invalid_calls {
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(sender),
+                    marketplace_contract.addr(),
+                    &msg,
+                    &funds,
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::InvalidAddress { .. }
+            ));
+        }
+
+        //a cw20 address that is not valid cannot price a listing
+        let hook_msg = Cw721HookMsg::SetListing {
+            cw20_contract: Some("Cw20".to_string()),
+            native_denom: None,
+            amount: 500,
+            expires_at: None,
+            payout_address: None,
+        };
+        let send_msg = nft::contract::Cw721ExecuteMsg::SendNft {
+            contract: marketplace_contract.addr().to_string(),
+            token_id: TOKEN_ID.to_string(),
+            msg: to_binary(&hook_msg).unwrap(),
+        };
+        let cosmos_msg = cw721_contract.call(send_msg).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert!(matches!(
+            err.root_cause().downcast_ref::<ContractError>(),
+            Some(ContractError::InvalidAddress { .. })
+        ));
+        let owner = get_owner_of(&app, &cw721_contract, TOKEN_ID.to_string());
+        assert_eq!(owner.owner, USER.to_string());
+    }
+
+    #[test]
+    fn ask_listed_by_approval() {
+        let (mut app, marketplace_id, _, cw721_id) = store_code();
+        let marketplace_contract = marketplace_instantiate(&mut app, marketplace_id);
+        let cw721_contract = cw721_instantiate(
+            &mut app,
+            cw721_id,
+            "NFT".to_string(),
+            "NFT".to_string(),
+            USER.to_string(),
+        );
+        for token_id in ["0", "1"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+        }
+        let call_nft = |app: &mut App, sender: &str, msg: nft::contract::Cw721ExecuteMsg| {
+            app.execute(Addr::unchecked(sender), cw721_contract.call(msg).unwrap())
+                .unwrap();
+        };
+        let execute = |app: &mut App, sender: &str, msg: &ExecuteMsg, funds: &[Coin]| {
+            app.execute_contract(
+                Addr::unchecked(sender),
+                marketplace_contract.addr(),
+                msg,
+                funds,
+            )
+        };
+        let set_ask = |token_id: &str| ExecuteMsg::SetAsk {
+            collection: cw721_contract.addr().to_string(),
+            token_id: token_id.to_string(),
+            price: Uint128::new(500),
+            payment: None,
+            expires: None,
+            payout_address: None,
+        };
+        let purchase = |token_id: &str| ExecuteMsg::PurchaseNative {
+            collection: cw721_contract.addr().to_string(),
+            token_id: token_id.to_string(),
+        };
+        let remove_stale = |token_id: &str| ExecuteMsg::RemoveStaleAsk {
+            collection: cw721_contract.addr().to_string(),
+            token_id: token_id.to_string(),
+        };
+
+        //only the owner can list, after approving the marketplace
+        let err = execute(&mut app, BUYER, &set_ask("0"), &[]).unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::UnauthorizedOwner {}
+        ));
+        let err = execute(&mut app, USER, &set_ask("0"), &[]).unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::NotApproved {}
+        ));
+        call_nft(
+            &mut app,
+            USER,
+            nft::contract::Cw721ExecuteMsg::Approve {
+                spender: marketplace_contract.addr().to_string(),
+                token_id: "0".to_string(),
+                expires: None,
+            },
+        );
+        execute(&mut app, USER, &set_ask("0"), &[]).unwrap();
+        let err = execute(&mut app, USER, &set_ask("0"), &[]).unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::AlreadyListed {}
+        ));
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+        let ask = get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            "0".to_string(),
+        )
+        .ask
+        .unwrap();
+        assert_eq!(ask.seller, USER.to_string());
+
+        //revoking the approval leaves a stale ask that cannot be bought and anyone can purge
+        let err = execute(&mut app, BIDDER, &remove_stale("0"), &[]).unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::AskNotStale {}
+        ));
+        call_nft(
+            &mut app,
+            USER,
+            nft::contract::Cw721ExecuteMsg::Revoke {
+                spender: marketplace_contract.addr().to_string(),
+                token_id: "0".to_string(),
+            },
+        );
+        let err = execute(&mut app, BUYER, &purchase("0"), &coins(500, NATIVE_DENOM)).unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::StaleAsk {}
+        ));
+        execute(&mut app, BIDDER, &remove_stale("0"), &[]).unwrap();
+        assert!(get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            "0".to_string()
+        )
+        .ask
+        .is_none());
+
+        //approving all tokens lists them, the purchase transfers from the wallet of the seller
+        call_nft(
+            &mut app,
+            USER,
+            nft::contract::Cw721ExecuteMsg::ApproveAll {
+                operator: marketplace_contract.addr().to_string(),
+                expires: None,
+            },
+        );
+        execute(&mut app, USER, &set_ask("0"), &[]).unwrap();
+        execute(&mut app, USER, &set_ask("1"), &[]).unwrap();
+        let remove_listing = ExecuteMsg::RemoveListing {
+            collection: cw721_contract.addr().to_string(),
+            token_id: "1".to_string(),
+        };
+        execute(&mut app, USER, &remove_listing, &[]).unwrap();
+        assert!(get_ask(
+            &app,
+            &marketplace_contract,
+            &cw721_contract,
+            "1".to_string()
+        )
+        .ask
+        .is_none());
+        execute(&mut app, USER, &set_ask("1"), &[]).unwrap();
+        execute(&mut app, BUYER, &purchase("0"), &coins(500, NATIVE_DENOM)).unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, BUYER.to_string());
+        let balance = app.wrap().query_balance(USER, NATIVE_DENOM).unwrap();
+        assert_eq!(balance.amount, Uint128::new(10500));
+
+        //a token moved out of the wallet of the seller leaves a stale ask
+        call_nft(
+            &mut app,
+            USER,
+            nft::contract::Cw721ExecuteMsg::TransferNft {
+                recipient: BIDDER.to_string(),
+                token_id: "1".to_string(),
+            },
+        );
+        let err = execute(&mut app, BUYER, &purchase("1"), &coins(500, NATIVE_DENOM)).unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::StaleAsk {}
+        ));
+        execute(&mut app, BUYER, &remove_stale("1"), &[]).unwrap();
+        let owner = get_owner_of(&app, &cw721_contract, "1".to_string());
+        assert_eq!(owner.owner, BIDDER.to_string());
+
+        //filling a collection bid or auctioning the token drops its ask
+        for token_id in ["2", "3"] {
+            mint_nft(
+                &mut app,
+                &cw721_contract,
+                token_id.to_string(),
+                None,
+                USER.to_string(),
+            );
+            execute(&mut app, USER, &set_ask(token_id), &[]).unwrap();
+        }
+        let set_collection_bid = ExecuteMsg::SetCollectionBid {
+            collection: cw721_contract.addr().to_string(),
+            expires: None,
+        };
+        execute(
+            &mut app,
+            BUYER,
+            &set_collection_bid,
+            &coins(300, NATIVE_DENOM),
+        )
+        .unwrap();
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            "2".to_string(),
+            Cw721HookMsg::AcceptCollectionBid {
+                bidder: BUYER.to_string(),
+            },
+        );
+        let end_time = app.block_info().time.plus_seconds(100);
+        send_nft_hook(
+            &mut app,
+            &marketplace_contract,
+            &cw721_contract,
+            "3".to_string(),
+            Cw721HookMsg::CreateAuction {
+                reserve_price: Uint128::new(500),
+                min_increment_bps: 0,
+                start_time: None,
+                end_time,
+                extension_secs: 0,
+                payment: Denom::Native(NATIVE_DENOM.to_string()),
+            },
+        );
+        for token_id in ["2", "3"] {
+            assert!(get_ask(
+                &app,
+                &marketplace_contract,
+                &cw721_contract,
+                token_id.to_string()
+            )
+            .ask
+            .is_none());
+        }
+    }
 }
@@ -1,49 +1,365 @@
-use cw20::Cw20ReceiveMsg;
+use cosmwasm_std::{Binary, Order, Timestamp, Uint128};
+use cw20::{Cw20ReceiveMsg, Denom};
 use cw721::Cw721ReceiveMsg;
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::{Ask, Cw721Deposits};
+use crate::state::{
+    Ask, Auction, Bid, CollectionBid, CollectionInfo, CollectionMode, Config, Cw721Deposits,
+    MetadataCriteria, PauseStatus, PaymentToken, Royalty, SealedAuction, SealedBid, TraitBid,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
-    pub native_denom: String
+    pub native_denom: String,
+    /// Further native denoms listings and auctions may be priced in
+    pub native_denoms: Vec<String>,
+    /// Defaults to the instantiator
+    pub admin: Option<String>,
+    /// Share of every sale paid to `fee_recipient`, in basis points
+    pub trading_fee_bps: u64,
+    pub fee_recipient: String,
+    /// Largest royalty paid on a sale, in basis points
+    pub max_royalty_bps: u64,
+    pub collection_mode: CollectionMode,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    /// Admin only, fields left unset are unchanged. The default native denom is fixed
+    /// since open bids hold it in escrow, open asks in a removed denom can still be
+    /// purchased
+    UpdateConfig {
+        native_denoms: Option<Vec<String>>,
+        trading_fee_bps: Option<u64>,
+        fee_recipient: Option<String>,
+        max_royalty_bps: Option<u64>,
+        collection_mode: Option<CollectionMode>,
+    },
+    /// Admin only, accepts a cw20 for listings and auctions
+    AddPaymentToken {
+        address: String,
+    },
+    /// Admin only, open asks in the token can still be purchased
+    RemovePaymentToken {
+        address: String,
+    },
+    /// Admin only, registers or replaces a collection
+    SetCollection {
+        address: String,
+        name: String,
+        verified: bool,
+        blocked: bool,
+    },
+    /// Admin only
+    RemoveCollection {
+        address: String,
+    },
+    /// Admin only, halts or resumes kinds of operations, fields left unset are unchanged
+    UpdatePauseStatus {
+        listing: Option<bool>,
+        purchasing: Option<bool>,
+        bidding: Option<bool>,
+        withdrawals: Option<bool>,
+    },
+    /// Admin only, the proposed address becomes admin once it accepts
+    ProposeNewAdmin {
+        admin: String,
+    },
+    /// Sent by the proposed admin to take over
+    AcceptAdmin {},
+    /// Admin only, leaves the config without an admin for good
+    RenounceAdmin {},
+    /// Collection minter only, royalty paid on sales of a collection that does not
+    /// answer cw2981 royalty queries
+    SetRoyalty {
+        collection: String,
+        recipient: String,
+        share_bps: u64,
+    },
+    /// Collection minter only
+    RemoveRoyalty {
+        collection: String,
+    },
     Receive(Cw20ReceiveMsg),
     ReceiveNft(Cw721ReceiveMsg),
     PurchaseNative {
         collection: String,
         token_id: String,
     },
+    /// Seller only, returns a deposited NFT or drops an ask listed by approval
     RemoveListing {
         collection: String,
         token_id: String,
     },
+    /// List an NFT that stays in the wallet of the sender, who must own it and have
+    /// approved the marketplace to transfer it, for the token or with `ApproveAll`
+    SetAsk {
+        collection: String,
+        token_id: String,
+        price: Uint128,
+        /// Default native denom if not set
+        payment: Option<Denom>,
+        /// Never expires if not set
+        expires: Option<Expiration>,
+        /// Receives the proceeds instead of the seller
+        payout_address: Option<String>,
+    },
+    /// Seller only, changes an ask in place, fields left unset are unchanged
+    UpdateAsk {
+        collection: String,
+        token_id: String,
+        price: Option<Uint128>,
+        payment: Option<Denom>,
+        expires: Option<Expiration>,
+    },
+    /// Anyone may send up to `limit` NFTs of expired asks back to their sellers
+    ReturnExpiredListings {
+        limit: Option<u32>,
+    },
+    /// Anyone may remove an ask listed by approval whose seller no longer owns the
+    /// token or has revoked the approval
+    RemoveStaleAsk {
+        collection: String,
+        token_id: String,
+    },
+    /// Offer the native coins sent for an NFT, replacing and refunding any previous bid
+    SetBid {
+        collection: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Withdraw a bid and refund its escrow
+    RemoveBid {
+        collection: String,
+        token_id: String,
+    },
+    /// Sell the NFT to the bidder; a token not held by the marketplace must
+    /// be approved for transfer by the sender first
+    AcceptBid {
+        collection: String,
+        token_id: String,
+        bidder: String,
+    },
+    /// Offer the native coins sent for any NFT of a collection, replacing and
+    /// refunding any previous collection bid
+    SetCollectionBid {
+        collection: String,
+        expires: Option<Expiration>,
+    },
+    /// Withdraw a collection bid and refund its escrow
+    RemoveCollectionBid {
+        collection: String,
+    },
+    /// Offer the native coins sent for any NFT of a collection whose metadata meets the criteria
+    SetTraitBid {
+        collection: String,
+        criteria: MetadataCriteria,
+        expires: Option<Expiration>,
+    },
+    /// Withdraw a trait bid and refund its escrow
+    RemoveTraitBid {
+        id: u64,
+    },
+    /// Outbid the current highest bid of a native auction with the coins sent
+    PlaceAuctionBid {
+        collection: String,
+        token_id: String,
+    },
+    /// Close an ended auction, paying the seller if the reserve was met and
+    /// returning the NFT otherwise
+    SettleAuction {
+        collection: String,
+        token_id: String,
+    },
+    /// Commit to a sealed bid, the coins sent are held as collateral
+    CommitSealedBid {
+        collection: String,
+        token_id: String,
+        /// sha256 of "{collection}:{token_id}:{bidder}:{amount}:{salt}"
+        commitment: Binary,
+    },
+    /// Reveal a committed bid during the reveal phase
+    RevealSealedBid {
+        collection: String,
+        token_id: String,
+        amount: Uint128,
+        salt: String,
+    },
+    /// Close a sealed-bid auction after its reveal phase, collateral of unrevealed
+    /// bids goes to the seller
+    SettleSealedAuction {
+        collection: String,
+        token_id: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    Config {},
+    PauseStatus {},
+    /// Cw20 tokens accepted for payment
+    PaymentTokens {},
+    /// Collection registered by the admin
+    Collection {
+        address: String,
+    },
+    Collections {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Royalty registered for a collection in the marketplace
+    Royalty {
+        collection: String,
+    },
     Cw721Deposits {
         owner: String,
         collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
     },
+    /// Listed NFT, including an expired ask that was not returned yet
     Ask {
         collection: String,
         token_id: String,
     },
-    GetAllAsks {}
+    /// Ask queries below leave out expired asks
+    GetAllAsks {
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    AsksBySeller {
+        seller: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    AsksByCollection {
+        collection: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Asks priced in the given cw20 address or native denom, or in the default native
+    /// denom if `payment_token` is not set
+    AsksByPaymentToken {
+        payment_token: Option<String>,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Fixed-price asks of a collection priced in the given cw20 address or native denom,
+    /// sorted by price within the inclusive `min`..`max` range. Declining asks are not
+    /// included, their price is given by `CurrentPrice`
+    AsksByPrice {
+        collection: String,
+        payment_token: Option<String>,
+        min: Option<Uint128>,
+        max: Option<Uint128>,
+        order: Option<SortOrder>,
+        /// (price, token_id) of the last ask of the previous page
+        start_after: Option<(Uint128, String)>,
+        limit: Option<u32>,
+    },
+    /// Cheapest fixed-price ask of a collection in the given cw20 address or native denom,
+    /// declining asks are not included
+    FloorPrice {
+        collection: String,
+        payment_token: Option<String>,
+    },
+    /// Price a buyer has to pay for a listed NFT at the current block time
+    CurrentPrice {
+        collection: String,
+        token_id: String,
+    },
+    BidsForToken {
+        collection: String,
+        token_id: String,
+        start_after: Option<(String, String, String)>,
+        limit: Option<u32>,
+    },
+    BidsByBidder {
+        bidder: String,
+        start_after: Option<(String, String, String)>,
+        limit: Option<u32>,
+    },
+    CollectionBid {
+        collection: String,
+        bidder: String,
+    },
+    /// Collection bids in the given cw20 address or native denom, the default native denom
+    /// if not set, highest price first
+    TopCollectionBids {
+        collection: String,
+        payment_token: Option<String>,
+        /// (price, bidder) of the last bid of the previous page
+        start_after: Option<(Uint128, String)>,
+        limit: Option<u32>,
+    },
+    TraitBid {
+        id: u64,
+    },
+    TraitBidsForCollection {
+        collection: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    TraitBidsByBidder {
+        bidder: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    Auction {
+        collection: String,
+        token_id: String,
+    },
+    /// Auctions still accepting bids, ending soonest first
+    ActiveAuctions {
+        /// (end_time, collection, token_id) of the last auction of the previous page
+        start_after: Option<(Timestamp, String, String)>,
+        limit: Option<u32>,
+    },
+    /// Auctions past their end time and waiting to be settled
+    EndedAuctions {
+        /// (end_time, collection, token_id) of the last auction of the previous page
+        start_after: Option<(Timestamp, String, String)>,
+        limit: Option<u32>,
+    },
+    SealedAuction {
+        collection: String,
+        token_id: String,
+    },
+    SealedBids {
+        collection: String,
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Order {
+        match order {
+            SortOrder::Ascending => Order::Ascending,
+            SortOrder::Descending => Order::Descending,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Cw721DepositResponse {
     pub deposits: Vec<(String, Cw721Deposits)>,
+    /// token_id to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -56,11 +372,145 @@ pub struct AskResponse {
 #[serde(rename_all = "snake_case")]
 pub struct GetAllAsksResponse {
     pub asks: Vec<((String, String), Ask)>,
+    /// (collection, token_id) to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<(String, String)>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub struct AsksResponse {
+    pub asks: Vec<Ask>,
+    /// (collection, token_id) to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AsksByPriceResponse {
+    pub asks: Vec<Ask>,
+    /// (price, token_id) to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<(Uint128, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct FloorPriceResponse {
+    pub price: Option<Uint128>,
+    pub ask: Option<Ask>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CurrentPriceResponse {
+    pub price: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BidsResponse {
+    pub bids: Vec<Bid>,
+    /// (collection, token_id, bidder) to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<(String, String, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CollectionBidResponse {
+    pub bid: Option<CollectionBid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CollectionBidsResponse {
+    pub bids: Vec<CollectionBid>,
+    /// (price, bidder) to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<(Uint128, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TraitBidResponse {
+    pub bid: Option<TraitBid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TraitBidsResponse {
+    pub bids: Vec<TraitBid>,
+    /// id to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuctionResponse {
+    pub auction: Option<Auction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuctionsResponse {
+    pub auctions: Vec<Auction>,
+    /// (end_time, collection, token_id) to pass as `start_after` for the next page,
+    /// if the page was full
+    pub next_start_after: Option<(Timestamp, String, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SealedAuctionResponse {
+    pub auction: Option<SealedAuction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SealedBidsResponse {
+    pub bids: Vec<SealedBid>,
+    /// Bidder to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConfigResponse {
+    pub config: Config,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PaymentTokensResponse {
+    pub tokens: Vec<PaymentToken>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CollectionResponse {
+    pub collection: Option<CollectionInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CollectionsResponse {
+    pub collections: Vec<CollectionInfo>,
+    /// Address to pass as `start_after` for the next page, if the page was full
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PauseStatusResponse {
+    pub pause_status: PauseStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RoyaltyResponse {
+    pub royalty: Option<Royalty>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -69,15 +519,87 @@ pub enum Cw20HookMsg {
         cw721_contract: String,
         token_id: String,
     },
+    /// Bid the tokens sent on an NFT, replacing and refunding any previous bid
+    SetBid {
+        collection: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Bid the tokens sent on any NFT of a collection, replacing and refunding
+    /// any previous collection bid
+    SetCollectionBid {
+        collection: String,
+        expires: Option<Expiration>,
+    },
+    /// Bid the tokens sent on any NFT of a collection whose metadata meets the criteria
+    SetTraitBid {
+        collection: String,
+        criteria: MetadataCriteria,
+        expires: Option<Expiration>,
+    },
+    /// Outbid the current highest bid of a cw20 auction with the tokens sent
+    PlaceAuctionBid {
+        collection: String,
+        token_id: String,
+    },
+    /// Commit to a sealed bid, the tokens sent are held as collateral
+    CommitSealedBid {
+        collection: String,
+        token_id: String,
+        commitment: Binary,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw721HookMsg {
+    /// List the NFT sent in the given cw20 or native denom, the default native denom if
+    /// neither is set. The sender of the NFT is the seller
     SetListing {
-        owner: String,
-        token_id: String,
         cw20_contract: Option<String>,
+        native_denom: Option<String>,
         amount: u128,
+        /// Never expires if not set
+        expires_at: Option<Expiration>,
+        /// Receives the proceeds instead of the seller
+        payout_address: Option<String>,
+    },
+    /// List the NFT sent at a price declining from `start_price` to `floor_price`
+    /// between `start_time` and `end_time`, linearly or every `step_secs`
+    SetDutchListing {
+        cw20_contract: Option<String>,
+        native_denom: Option<String>,
+        start_price: Uint128,
+        floor_price: Uint128,
+        /// Defaults to the current block time
+        start_time: Option<Timestamp>,
+        end_time: Timestamp,
+        step_secs: Option<u64>,
+        /// Never expires if not set
+        expires_at: Option<Expiration>,
+        /// Receives the proceeds instead of the seller
+        payout_address: Option<String>,
+    },
+    /// Sell the NFT sent to the given collection bid
+    AcceptCollectionBid { bidder: String },
+    /// Sell the NFT sent to the given trait bid, if its metadata meets the criteria
+    AcceptTraitBid { id: u64 },
+    /// Put the NFT sent up for an English auction
+    CreateAuction {
+        reserve_price: Uint128,
+        min_increment_bps: u64,
+        /// Defaults to the current block time
+        start_time: Option<Timestamp>,
+        end_time: Timestamp,
+        extension_secs: u64,
+        payment: Denom,
+    },
+    /// Put the NFT sent up for a sealed-bid auction, committing until `commit_end`
+    /// and revealing until `reveal_end`
+    CreateSealedAuction {
+        reserve_price: Uint128,
+        commit_end: Timestamp,
+        reveal_end: Timestamp,
+        payment: Denom,
     },
 }
@@ -1,33 +1,481 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, BlockInfo, Timestamp, Uint128};
+use cw20::Denom;
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::Expiration;
+use nft::contract::Metadata;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub native_denom: String
+    /// Default denom of listings, and the denom native bids are escrowed in
+    pub native_denom: String,
+    /// Further native denoms listings and auctions may be priced in
+    pub native_denoms: Vec<String>,
+    /// Address allowed to update the config, none once renounced
+    pub admin: Option<Addr>,
+    /// Address proposed as the next admin, until it accepts
+    pub pending_admin: Option<Addr>,
+    /// Share of every sale paid to `fee_recipient`, in basis points
+    pub trading_fee_bps: u64,
+    pub fee_recipient: Addr,
+    /// Largest royalty paid on a sale, in basis points
+    pub max_royalty_bps: u64,
+    /// Whether unregistered collections may trade
+    pub collection_mode: CollectionMode,
+}
+
+impl Config {
+    pub fn accepts_native_denom(&self, denom: &str) -> bool {
+        self.native_denom == denom || self.native_denoms.iter().any(|accepted| accepted == denom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionMode {
+    /// Only collections registered and not blocked may trade
+    Allowlist,
+    /// Any collection may trade unless registered as blocked
+    Open,
+}
+
+/// Cw20 accepted for payment, with the token info read when the admin added it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PaymentToken {
+    pub address: Addr,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Collection registered by the admin
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionInfo {
+    pub address: Addr,
+    /// Display name
+    pub name: String,
+    pub verified: bool,
+    pub blocked: bool,
+}
+
+/// Kinds of operations halted by the admin, removing a listing or a bid is never halted
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PauseStatus {
+    /// Listings and auctions
+    pub listing: bool,
+    /// Purchases, accepted bids and auction settlements
+    pub purchasing: bool,
+    /// Bids and sealed bid commitments
+    pub bidding: bool,
+    /// Expired listings returned and stale asks removed by anyone
+    pub withdrawals: bool,
+}
+
+/// Royalty registered by the minter of a collection that does not implement cw2981
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Royalty {
+    pub collection: Addr,
+    pub recipient: Addr,
+    pub share_bps: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Cw721Deposits {
-    pub owner: String,
-    pub collection: String,
+    pub owner: Addr,
+    pub collection: Addr,
     pub token_id: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Ask {
-    pub collection: String,
+    pub collection: Addr,
     pub token_id: String,
-    pub seller: String,
+    pub seller: Addr,
+    /// Fixed price, or starting price of a declining ask
     pub price: Uint128,
-    pub cw20_contract: Option<String>,
+    /// Native denom or cw20 the ask must be paid in
+    pub payment: Denom,
+    /// Makes the ask a Dutch auction whose price declines from `price` to the floor
+    pub decline: Option<PriceDecline>,
+    /// After which the ask can no longer be purchased and the NFT can be returned
+    pub expires_at: Expiration,
+    /// Receives the proceeds of the sale instead of the seller
+    pub payout_address: Option<Addr>,
+}
+
+impl Ask {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.expires_at.is_expired(block)
+    }
+
+    /// Address the proceeds of a sale are paid to
+    pub fn payee(&self) -> &Addr {
+        self.payout_address.as_ref().unwrap_or(&self.seller)
+    }
+
+    /// Price a buyer has to pay at the given block time
+    pub fn current_price(&self, time: Timestamp) -> Uint128 {
+        match &self.decline {
+            Some(decline) => decline.price_at(self.price, time),
+            None => self.price,
+        }
+    }
+}
+
+/// Schedule of a declining price, linear or lowered every `step_secs`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceDecline {
+    pub floor_price: Uint128,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    pub step_secs: Option<u64>,
+}
+
+impl PriceDecline {
+    pub fn price_at(&self, start_price: Uint128, time: Timestamp) -> Uint128 {
+        if time <= self.start_time {
+            return start_price;
+        }
+        if time >= self.end_time {
+            return self.floor_price;
+        }
+        let duration = self.end_time.seconds() - self.start_time.seconds();
+        let mut elapsed = time.seconds() - self.start_time.seconds();
+        if let Some(step_secs) = self.step_secs {
+            elapsed -= elapsed % step_secs;
+        }
+        let decrease = (start_price - self.floor_price).multiply_ratio(elapsed, duration);
+        start_price - decrease
+    }
+}
+
+/// An offer escrowed by a buyer for a specific NFT, listed or not
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bid {
+    pub collection: Addr,
+    pub token_id: String,
+    pub bidder: Addr,
+    pub price: Uint128,
+    /// native denom or cw20 the bid is escrowed in
+    pub payment: Denom,
+    pub expires: Expiration,
+}
+
+/// An offer escrowed by a buyer for any NFT of a collection
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionBid {
+    pub collection: Addr,
+    pub bidder: Addr,
+    pub price: Uint128,
+    /// native denom or cw20 the bid is escrowed in
+    pub payment: Denom,
+    pub expires: Expiration,
+}
+
+/// Condition on the on-chain metadata of a token, checked when a trait bid is filled
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataCriteria {
+    Trait { trait_type: String, value: String },
+    Skill { skill: String },
+    Cohort { cohort: String },
+}
+
+impl MetadataCriteria {
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        match self {
+            MetadataCriteria::Trait { trait_type, value } => metadata
+                .attributes
+                .iter()
+                .flatten()
+                .any(|attr| &attr.trait_type == trait_type && &attr.value == value),
+            MetadataCriteria::Skill { skill } => {
+                metadata.skills.iter().flatten().any(|s| s == skill)
+            }
+            MetadataCriteria::Cohort { cohort } => metadata.cohort.as_ref() == Some(cohort),
+        }
+    }
+}
+
+/// An offer escrowed by a buyer for any NFT of a collection whose metadata meets the criteria
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TraitBid {
+    pub id: u64,
+    pub collection: Addr,
+    pub bidder: Addr,
+    pub price: Uint128,
+    /// native denom or cw20 the bid is escrowed in
+    pub payment: Denom,
+    pub criteria: MetadataCriteria,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionBid {
+    pub bidder: Addr,
+    pub amount: Uint128,
+}
+
+/// An English auction of an NFT held by the marketplace
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Auction {
+    pub collection: Addr,
+    pub token_id: String,
+    pub seller: Addr,
+    pub payment: Denom,
+    /// Lowest winning bid, lower bids are accepted but return the NFT at settlement
+    pub reserve_price: Uint128,
+    /// Minimum raise over the current highest bid, in basis points
+    pub min_increment_bps: u64,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    /// A bid placed within this many seconds of the end pushes the end back to that distance
+    pub extension_secs: u64,
+    pub highest_bid: Option<AuctionBid>,
+}
+
+/// A sealed-bid auction of an NFT held by the marketplace. Bids are committed as hashes
+/// until `commit_end` and revealed until `reveal_end`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SealedAuction {
+    pub collection: Addr,
+    pub token_id: String,
+    pub seller: Addr,
+    pub payment: Denom,
+    /// Lowest winning bid, lower reveals are refunded
+    pub reserve_price: Uint128,
+    pub commit_end: Timestamp,
+    pub reveal_end: Timestamp,
+    /// Highest bid revealed so far
+    pub highest_bid: Option<AuctionBid>,
+    /// Number of bids committed, bounding the work of settling the auction
+    pub bid_count: u32,
+}
+
+/// Commitment of a bidder to a sealed-bid auction, backed by collateral that has to
+/// cover the amount revealed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SealedBid {
+    pub bidder: Addr,
+    /// sha256 of "{collection}:{token_id}:{bidder}:{amount}:{salt}"
+    pub commitment: Binary,
+    pub collateral: Uint128,
+    pub revealed: Option<Uint128>,
+}
+
+/// Index key of the denom an ask or collection bid is priced in, the native denom or the
+/// cw20 contract address
+pub fn denom_key(denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => denom.clone(),
+        Denom::Cw20(cw20_contract) => cw20_contract.to_string(),
+    }
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
+
+pub const PAUSE_STATUS: Item<PauseStatus> = Item::new("pause_status");
+
+//key is cw20_contract
+pub const PAYMENT_TOKENS: Map<&str, PaymentToken> = Map::new("payment_tokens");
+
+//key is cw721_contract
+pub const COLLECTIONS: Map<&str, CollectionInfo> = Map::new("collections");
+
+//key is cw721_contract
+pub const ROYALTIES: Map<&str, Royalty> = Map::new("royalties");
 //contract, owner, token_id
 pub const CW721_DEPOSITS: Map<(&str, &str, &str), Cw721Deposits> = Map::new("cw721deposits");
 
+/// Owned (collection, token_id) primary key of an ask
+pub type AskKey = (String, String);
+
+pub const EXPIRES_AT_HEIGHT: u8 = 0;
+pub const EXPIRES_AT_TIME: u8 = 1;
+
+/// Index key of an expiration, heights and times are kept apart and an ask that never
+/// expires sorts after every time
+pub fn expiration_key(expires: &Expiration) -> (u8, u64) {
+    match expires {
+        Expiration::AtHeight(height) => (EXPIRES_AT_HEIGHT, *height),
+        Expiration::AtTime(time) => (EXPIRES_AT_TIME, time.nanos()),
+        Expiration::Never {} => (EXPIRES_AT_TIME, u64::MAX),
+    }
+}
+
+pub struct AskIndexes<'a> {
+    pub seller: MultiIndex<'a, String, Ask, AskKey>,
+    pub collection: MultiIndex<'a, String, Ask, AskKey>,
+    pub payment_token: MultiIndex<'a, String, Ask, AskKey>,
+    // (collection, payment token), price
+    pub price: MultiIndex<'a, ((String, String), u128), Ask, AskKey>,
+    // (expiration kind, height or time in nanos)
+    pub expires_at: MultiIndex<'a, (u8, u64), Ask, AskKey>,
+}
+
+impl<'a> IndexList<Ask> for AskIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Ask>> + '_> {
+        let v: Vec<&dyn Index<Ask>> = vec![
+            &self.seller,
+            &self.collection,
+            &self.payment_token,
+            &self.price,
+            &self.expires_at,
+        ];
+        Box::new(v.into_iter())
+    }
+}
+
 //key can be cw721_contract, token_id
-pub const ASKS: Map<(&str, &str), Ask> = Map::new("asks");
+pub fn asks<'a>() -> IndexedMap<'a, (&'a str, &'a str), Ask, AskIndexes<'a>> {
+    let indexes = AskIndexes {
+        seller: MultiIndex::new(|ask: &Ask| ask.seller.to_string(), "asks", "asks__seller"),
+        collection: MultiIndex::new(
+            |ask: &Ask| ask.collection.to_string(),
+            "asks",
+            "asks__collection",
+        ),
+        payment_token: MultiIndex::new(
+            |ask: &Ask| denom_key(&ask.payment),
+            "asks",
+            "asks__payment_token",
+        ),
+        price: MultiIndex::new(
+            |ask: &Ask| {
+                (
+                    (ask.collection.to_string(), denom_key(&ask.payment)),
+                    ask.price.u128(),
+                )
+            },
+            "asks",
+            "asks__price",
+        ),
+        expires_at: MultiIndex::new(
+            |ask: &Ask| expiration_key(&ask.expires_at),
+            "asks",
+            "asks__expires_at",
+        ),
+    };
+    IndexedMap::new("asks", indexes)
+}
+
+/// Owned (collection, token_id, bidder) primary key of a bid
+pub type BidKey = (String, String, String);
+
+pub struct BidIndexes<'a> {
+    pub bidder: MultiIndex<'a, String, Bid, BidKey>,
+}
+
+impl<'a> IndexList<Bid> for BidIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Bid>> + '_> {
+        let v: Vec<&dyn Index<Bid>> = vec![&self.bidder];
+        Box::new(v.into_iter())
+    }
+}
+
+//key is cw721_contract, token_id, bidder
+pub fn bids<'a>() -> IndexedMap<'a, (&'a str, &'a str, &'a str), Bid, BidIndexes<'a>> {
+    let indexes = BidIndexes {
+        bidder: MultiIndex::new(|bid: &Bid| bid.bidder.to_string(), "bids", "bids__bidder"),
+    };
+    IndexedMap::new("bids", indexes)
+}
+
+/// Owned (collection, bidder) primary key of a collection bid
+pub type CollectionBidKey = (String, String);
+
+pub struct CollectionBidIndexes<'a> {
+    // (collection, denom), price
+    pub price: MultiIndex<'a, ((String, String), u128), CollectionBid, CollectionBidKey>,
+}
+
+impl<'a> IndexList<CollectionBid> for CollectionBidIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<CollectionBid>> + '_> {
+        let v: Vec<&dyn Index<CollectionBid>> = vec![&self.price];
+        Box::new(v.into_iter())
+    }
+}
+
+//key is cw721_contract, bidder
+pub fn collection_bids<'a>(
+) -> IndexedMap<'a, (&'a str, &'a str), CollectionBid, CollectionBidIndexes<'a>> {
+    let indexes = CollectionBidIndexes {
+        price: MultiIndex::new(
+            |bid: &CollectionBid| {
+                (
+                    (bid.collection.to_string(), denom_key(&bid.payment)),
+                    bid.price.u128(),
+                )
+            },
+            "collection_bids",
+            "collection_bids__price",
+        ),
+    };
+    IndexedMap::new("collection_bids", indexes)
+}
+
+pub struct TraitBidIndexes<'a> {
+    pub collection: MultiIndex<'a, String, TraitBid, u64>,
+    pub bidder: MultiIndex<'a, String, TraitBid, u64>,
+}
+
+impl<'a> IndexList<TraitBid> for TraitBidIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<TraitBid>> + '_> {
+        let v: Vec<&dyn Index<TraitBid>> = vec![&self.collection, &self.bidder];
+        Box::new(v.into_iter())
+    }
+}
+
+pub const TRAIT_BID_COUNT: Item<u64> = Item::new("trait_bid_count");
+
+//key is the trait bid id
+pub fn trait_bids<'a>() -> IndexedMap<'a, u64, TraitBid, TraitBidIndexes<'a>> {
+    let indexes = TraitBidIndexes {
+        collection: MultiIndex::new(
+            |bid: &TraitBid| bid.collection.to_string(),
+            "trait_bids",
+            "trait_bids__collection",
+        ),
+        bidder: MultiIndex::new(
+            |bid: &TraitBid| bid.bidder.to_string(),
+            "trait_bids",
+            "trait_bids__bidder",
+        ),
+    };
+    IndexedMap::new("trait_bids", indexes)
+}
+
+/// Owned (collection, token_id) primary key of an auction
+pub type AuctionKey = (String, String);
+
+pub struct AuctionIndexes<'a> {
+    // end time in nanoseconds
+    pub end_time: MultiIndex<'a, u64, Auction, AuctionKey>,
+}
+
+impl<'a> IndexList<Auction> for AuctionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Auction>> + '_> {
+        let v: Vec<&dyn Index<Auction>> = vec![&self.end_time];
+        Box::new(v.into_iter())
+    }
+}
+
+//key is cw721_contract, token_id
+pub fn auctions<'a>() -> IndexedMap<'a, (&'a str, &'a str), Auction, AuctionIndexes<'a>> {
+    let indexes = AuctionIndexes {
+        end_time: MultiIndex::new(
+            |auction: &Auction| auction.end_time.nanos(),
+            "auctions",
+            "auctions__end_time",
+        ),
+    };
+    IndexedMap::new("auctions", indexes)
+}
+
+//key is cw721_contract, token_id
+pub const SEALED_AUCTIONS: Map<(&str, &str), SealedAuction> = Map::new("sealed_auctions");
+
+//key is cw721_contract, token_id, bidder
+pub const SEALED_BIDS: Map<(&str, &str, &str), SealedBid> = Map::new("sealed_bids");
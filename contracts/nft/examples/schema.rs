@@ -9,6 +9,7 @@ use cw721::{
 };
 use cw721_base::{InstantiateMsg, MinterResponse};
 use nft::contract::{Cw721ExecuteMsg, Extension, QueryMsg};
+use nft::msg::{CheckRoyaltiesResponse, RoyaltiesInfoResponse};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -37,4 +38,6 @@ fn main() {
     export_schema(&schema_for!(NumTokensResponse), &out_dir);
     export_schema(&schema_for!(OwnerOfResponse), &out_dir);
     export_schema(&schema_for!(TokensResponse), &out_dir);
+    export_schema(&schema_for!(RoyaltiesInfoResponse), &out_dir);
+    export_schema(&schema_for!(CheckRoyaltiesResponse), &out_dir);
 }
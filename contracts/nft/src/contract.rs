@@ -30,21 +30,27 @@ pub struct Metadata {
     pub skills: Option<Vec<String>>,
     pub github_url: Option<String>,
     pub is_for_hire: bool,
+    /// cw2981 royalty, as a percentage of the sale price
+    pub royalty_percentage: Option<u64>,
+    /// cw2981 royalty recipient
+    pub royalty_payment_address: Option<String>,
 }
 
 pub type Extension = Option<Metadata>;
 
 pub type Cw721MetadaNonTransferableContract<'a> = Cw721Contract<'a, Extension, Empty>;
 pub type Cw721ExecuteMsg = cw721_base::ExecuteMsg<Extension>;
-pub type QueryMsg = cw721_base::QueryMsg;
+pub use crate::msg::QueryMsg;
 
 #[cfg(not(feature = "library"))]
 pub mod entry {
     use super::*;
 
-    use crate::msg::ExecuteMsg;
+    use crate::msg::{CheckRoyaltiesResponse, ExecuteMsg, RoyaltiesInfoResponse};
     use cosmwasm_std::entry_point;
-    use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cosmwasm_std::{
+        to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    };
     use cw721_base::ContractError;
 
     // This makes a conscious choice on the various generics used by the contract
@@ -76,6 +82,7 @@ pub mod entry {
     ) -> Result<Response, ContractError> {
         match msg {
             ExecuteMsg::Mint(mint_msg) => {
+                validate_royalty(deps.as_ref(), &mint_msg.extension)?;
                 Cw721MetadaNonTransferableContract::default().mint(deps, env, info, mint_msg)
             }
             ExecuteMsg::UpdateMetadata {
@@ -88,7 +95,119 @@ pub mod entry {
 
     #[entry_point]
     pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-        Cw721MetadaNonTransferableContract::default().query(deps, env, msg)
+        let cw721_msg = match msg {
+            QueryMsg::RoyaltyInfo {
+                token_id,
+                sale_price,
+            } => return to_binary(&query_royalty_info(deps, token_id, sale_price)?),
+            QueryMsg::CheckRoyalties {} => {
+                return to_binary(&CheckRoyaltiesResponse {
+                    royalty_payments: true,
+                })
+            }
+
+            QueryMsg::OwnerOf {
+                token_id,
+                include_expired,
+            } => cw721_base::QueryMsg::OwnerOf {
+                token_id,
+                include_expired,
+            },
+            QueryMsg::Approval {
+                token_id,
+                spender,
+                include_expired,
+            } => cw721_base::QueryMsg::Approval {
+                token_id,
+                spender,
+                include_expired,
+            },
+            QueryMsg::Approvals {
+                token_id,
+                include_expired,
+            } => cw721_base::QueryMsg::Approvals {
+                token_id,
+                include_expired,
+            },
+            QueryMsg::AllOperators {
+                owner,
+                include_expired,
+                start_after,
+                limit,
+            } => cw721_base::QueryMsg::AllOperators {
+                owner,
+                include_expired,
+                start_after,
+                limit,
+            },
+            QueryMsg::NumTokens {} => cw721_base::QueryMsg::NumTokens {},
+            QueryMsg::ContractInfo {} => cw721_base::QueryMsg::ContractInfo {},
+            QueryMsg::NftInfo { token_id } => cw721_base::QueryMsg::NftInfo { token_id },
+            QueryMsg::AllNftInfo {
+                token_id,
+                include_expired,
+            } => cw721_base::QueryMsg::AllNftInfo {
+                token_id,
+                include_expired,
+            },
+            QueryMsg::Tokens {
+                owner,
+                start_after,
+                limit,
+            } => cw721_base::QueryMsg::Tokens {
+                owner,
+                start_after,
+                limit,
+            },
+            QueryMsg::AllTokens { start_after, limit } => {
+                cw721_base::QueryMsg::AllTokens { start_after, limit }
+            }
+            QueryMsg::Minter {} => cw721_base::QueryMsg::Minter {},
+        };
+        Cw721MetadaNonTransferableContract::default().query(deps, env, cw721_msg)
+    }
+
+    fn query_royalty_info(
+        deps: Deps,
+        token_id: String,
+        sale_price: Uint128,
+    ) -> StdResult<RoyaltiesInfoResponse> {
+        let token = Cw721MetadaNonTransferableContract::default()
+            .tokens
+            .load(deps.storage, &token_id)?;
+        let metadata = token.extension.unwrap_or_default();
+        // no royalty is owed without an address to pay it to
+        let royalty_amount = match metadata.royalty_percentage {
+            Some(percentage) if metadata.royalty_payment_address.is_some() => {
+                sale_price.multiply_ratio(percentage, 100u128)
+            }
+            _ => Uint128::zero(),
+        };
+        Ok(RoyaltiesInfoResponse {
+            address: metadata.royalty_payment_address.unwrap_or_default(),
+            royalty_amount,
+        })
+    }
+
+    fn validate_royalty(deps: Deps, extension: &Extension) -> Result<(), ContractError> {
+        if let Some(metadata) = extension {
+            if metadata.royalty_percentage.unwrap_or_default() > 100 {
+                return Err(StdError::generic_err("Royalty percentage cannot exceed 100").into());
+            }
+            match &metadata.royalty_payment_address {
+                Some(address) => {
+                    deps.api.addr_validate(address)?;
+                }
+                None if metadata.royalty_percentage.unwrap_or_default() > 0 => {
+                    return Err(StdError::generic_err(
+                        "Royalty percentage requires a payment address",
+                    )
+                    .into());
+                }
+                None => {}
+            }
+        }
+        Ok(())
     }
 
     fn execute_update_on_chain_metadata(
@@ -99,6 +218,8 @@ pub mod entry {
         token_uri: String,
         metadata: Metadata
     ) -> Result<Response, ContractError> {
+        let metadata = Some(metadata);
+        validate_royalty(deps.as_ref(), &metadata)?;
         let tract = Cw721MetadaNonTransferableContract::default();
         let minter = tract.minter.load(deps.storage)?;
         if info.sender != minter {
@@ -108,7 +229,7 @@ pub mod entry {
                 .tokens
                 .update(deps.storage, &token_id, |token| match token {
                     Some(mut token_info) => {
-                        token_info.extension = Some(metadata);
+                        token_info.extension = metadata;
                         token_info.token_uri = Some(token_uri.clone());
                         Ok(token_info)
                     },
@@ -164,4 +285,90 @@ mod tests {
         assert_eq!(res.token_uri, mint_msg.token_uri);
         assert_eq!(res.extension, mint_msg.extension);
     }
+
+    #[test]
+    fn query_cw2981_royalties() {
+        use crate::msg::{CheckRoyaltiesResponse, ExecuteMsg, RoyaltiesInfoResponse};
+        use cosmwasm_std::{from_binary, Uint128};
+        use cw721::OwnerOfResponse;
+
+        let mut deps = mock_dependencies();
+        let info = mock_info(CREATOR, &[]);
+        let init_msg = InstantiateMsg {
+            name: "SpaceShips".to_string(),
+            symbol: "SPACE".to_string(),
+            minter: CREATOR.to_string(),
+        };
+        entry::instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
+
+        let mint_msg = |token_id: &str, royalty_percentage| {
+            ExecuteMsg::Mint(MintMsg {
+                token_id: token_id.to_string(),
+                owner: "john".to_string(),
+                token_uri: None,
+                extension: Some(Metadata {
+                    royalty_percentage: Some(royalty_percentage),
+                    royalty_payment_address: Some("artist".to_string()),
+                    ..Metadata::default()
+                }),
+            })
+        };
+        entry::execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            mint_msg("Enterprise", 10),
+        )
+        .unwrap();
+        // royalties above 100% are rejected
+        entry::execute(deps.as_mut(), mock_env(), info.clone(), mint_msg("Voyager", 101))
+            .unwrap_err();
+        // so is a royalty without an address to pay it to
+        let no_address = ExecuteMsg::Mint(MintMsg {
+            token_id: "Defiant".to_string(),
+            owner: "john".to_string(),
+            token_uri: None,
+            extension: Some(Metadata {
+                royalty_percentage: Some(5),
+                ..Metadata::default()
+            }),
+        });
+        entry::execute(deps.as_mut(), mock_env(), info, no_address).unwrap_err();
+
+        let res: RoyaltiesInfoResponse = from_binary(
+            &entry::query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RoyaltyInfo {
+                    token_id: "Enterprise".to_string(),
+                    sale_price: Uint128::new(250),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.address, "artist");
+        assert_eq!(res.royalty_amount, Uint128::new(25));
+
+        let res: CheckRoyaltiesResponse = from_binary(
+            &entry::query(deps.as_ref(), mock_env(), QueryMsg::CheckRoyalties {}).unwrap(),
+        )
+        .unwrap();
+        assert!(res.royalty_payments);
+
+        // cw721 queries are still answered
+        let res: OwnerOfResponse = from_binary(
+            &entry::query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::OwnerOf {
+                    token_id: "Enterprise".to_string(),
+                    include_expired: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.owner, "john");
+    }
 }
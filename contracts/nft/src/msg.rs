@@ -1,3 +1,4 @@
+use cosmwasm_std::Uint128;
 use cw721_base::MintMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -6,9 +7,73 @@ use crate::contract::Metadata;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg<T> {
     /// Mint a new NFT, can only be called by the contract minter
     Mint(MintMsg<T>),
     /// Updates metadata of the NFT
     UpdateMetadata { token_id: String, token_uri: String, metadata: Metadata },
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Royalty owed on a sale of the token at `sale_price`, as defined by cw2981
+    /// Return type: RoyaltiesInfoResponse
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    /// Whether the contract implements cw2981 royalties
+    /// Return type: CheckRoyaltiesResponse
+    CheckRoyalties {},
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    Approval {
+        token_id: String,
+        spender: String,
+        include_expired: Option<bool>,
+    },
+    Approvals {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    AllOperators {
+        owner: String,
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    NumTokens {},
+    ContractInfo {},
+    NftInfo {
+        token_id: String,
+    },
+    AllNftInfo {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    Minter {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckRoyaltiesResponse {
+    pub royalty_payments: bool,
+}